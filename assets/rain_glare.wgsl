@@ -1,31 +1,65 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
-
-struct RainGlareSettings {
-    intensity: f32,
-    threshold: f32,
-    streak_length_px: f32,
-    rain_density: f32,
-
-    wind: vec2<f32>,
-    speed: f32,
-    time: f32,
-
-    pattern_scale: f32,
-    mask_thickness_px: f32,
-    snap_to_pixel: f32,
-    tail_quant_steps: f32,
-
-    view_angle_factor: f32,
-};
+#import rain_glare::types::RainGlareSettings
 
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var screen_sampler: sampler;
 @group(0) @binding(2) var<uniform> settings: RainGlareSettings;
+// Alpha > 0 suppresses streaks (e.g. under opaque UI). Transparent black when unused.
+@group(0) @binding(3) var exclusion_mask: texture_2d<f32>;
+// Prepass depth (reverse-Z, 0 at the far plane). All zero when there is no usable prepass.
+@group(0) @binding(4) var depth_texture: texture_2d<f32>;
+// Prepass motion vectors (UV delta to the previous frame). All zero when unavailable.
+@group(0) @binding(5) var motion_vectors: texture_2d<f32>;
+// Prepass world normals (encoded * 0.5 + 0.5, alpha 1 on geometry). All zero when unavailable.
+@group(0) @binding(6) var normal_texture: texture_2d<f32>;
+// Last frame's accumulated wetness (red channel). All zero without `WETNESS`.
+@group(0) @binding(7) var wetness_texture: texture_2d<f32>;
+// Streak mask from the compute prepass (red channel), at native size for `COMPUTE_MASK` or
+// at the virtual resolution. A 1x1 zero texture otherwise.
+@group(0) @binding(8) var mask_texture: texture_2d<f32>;
+#ifdef MASK_PREPASS
+// Output of the `compute_mask` prepass, which binds only this and `settings`.
+@group(0) @binding(9) var mask_out: texture_storage_2d<r32float, write>;
+#endif
+
+#ifdef WETNESS
+struct RainGlareOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) wetness: vec4<f32>,
+};
+#endif
 
 fn luma(c: vec3<f32>) -> f32 {
     return dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
 }
 
+// Brightness used by the bright-pass; standard luminance with the default weights.
+fn threshold_luma(c: vec3<f32>) -> f32 {
+    return dot(c, settings.threshold_color_weights);
+}
+
+// Bright-pass brightness at `uv`, optionally bled over a small box around it so sub-pixel
+// highlights reach their neighbours. `c` is the already-fetched color at `uv`.
+fn bright_pass_luma(c: vec3<f32>, uv: vec2<f32>, dims: vec2<f32>) -> f32 {
+    let center = threshold_luma(c);
+#ifdef MOBILE
+    // No extra taps on the cheap path.
+    return center;
+#else
+    if (settings.highlight_bleed_px <= 0.0) {
+        return center;
+    }
+
+    let o = vec2<f32>(settings.highlight_bleed_px) / dims;
+    let box = center
+        + threshold_luma(textureSample(screen_texture, screen_sampler, uv + vec2<f32>(o.x, o.y)).rgb)
+        + threshold_luma(textureSample(screen_texture, screen_sampler, uv + vec2<f32>(-o.x, o.y)).rgb)
+        + threshold_luma(textureSample(screen_texture, screen_sampler, uv + vec2<f32>(o.x, -o.y)).rgb)
+        + threshold_luma(textureSample(screen_texture, screen_sampler, uv - o).rgb);
+    return max(center, box / 5.0);
+#endif
+}
+
 fn hash11(x: f32) -> f32 {
     return fract(sin(x) * 43758.5453123);
 }
@@ -35,6 +69,43 @@ fn hash12(p: vec2<f32>) -> f32 {
     return fract(sin(h) * 43758.5453123);
 }
 
+// Smooth value noise in 0..1.
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+
+    let a = hash12(i);
+    let b = hash12(i + vec2<f32>(1.0, 0.0));
+    let c = hash12(i + vec2<f32>(0.0, 1.0));
+    let d = hash12(i + vec2<f32>(1.0, 1.0));
+
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// 1 where the glass is wet, 0 inside dry patches.
+fn dry_patch_mask(uv: vec2<f32>, dims: vec2<f32>, scale_px: f32, amount: f32, seed: f32) -> f32 {
+    if (amount <= 0.0) {
+        return 1.0;
+    }
+
+    let n = value_noise(uv * dims / max(scale_px, 1.0) + vec2<f32>(seed * 37.0, seed * 59.0));
+    let a = clamp(amount, 0.0, 1.0);
+    return smoothstep(a - 0.1, a + 0.1, n);
+}
+
+#ifdef ANALYTIC_AA
+// Pattern-space width of one pixel, measured on a coordinate that is linear across it.
+fn aa_footprint(x: f32) -> f32 {
+#ifdef MASK_PREPASS
+    // Compute shaders have no derivatives; one pattern pixel per evaluated pixel.
+    return 1.0;
+#else
+    return max(fwidth(x), 1e-4);
+#endif
+}
+#endif
+
 fn lens_rain_mask(
     uv: vec2<f32>,
     dims: vec2<f32>,
@@ -45,6 +116,13 @@ fn lens_rain_mask(
     pattern_scale: f32,
     thickness_px: f32,
     tail_quant_steps: f32,
+    edge_softness_px: f32,
+    sparkle_amount: f32,
+    head_fade: f32,
+    tail_fade: f32,
+    seed: f32,
+    cross_blur_px: f32,
+    spacing_scale: f32,
 ) -> f32 {
     let p = uv * dims;
     let perp = vec2<f32>(-dir.y, dir.x);
@@ -54,10 +132,11 @@ fn lens_rain_mask(
 
     // Smaller pattern => smaller spacing/period.
     let s = max(pattern_scale, 0.001);
-    let spacing = 7.0 / s;
+    let spacing = 7.0 * max(spacing_scale, 0.01) / s;
     let period  = 46.0 / s;
 
-    let line_id = floor(u / spacing);
+    // The seed shifts which hash each line reads (0 keeps the original pattern).
+    let line_id = floor(u / spacing) + seed * 101.0;
 
     // Randomize active lines based on density.
     let r = hash11(line_id * 12.9898 + 78.233);
@@ -68,19 +147,163 @@ fn lens_rain_mask(
 
     // HARD EDGE width (no smoothing). Clamp thickness so it can't exceed half the cell.
     let thick = min(max(thickness_px, 0.1), spacing * 0.49);
-    let width = 1.0 - step(thick, dist); // 1 inside, 0 outside
+    var width = 1.0 - step(thick, dist); // 1 inside, 0 outside
+#ifdef ANALYTIC_AA
+    // Coverage of the line over one pixel's footprint (`u` is continuous, unlike `dist`).
+    width = clamp((thick - dist) / aa_footprint(u) + 0.5, 0.0, 1.0);
+#endif
+
+    // Optional soft edge, centered on the hard edge.
+    if (edge_softness_px > 0.0) {
+        let half_soft = edge_softness_px * 0.5;
+        width = 1.0 - smoothstep(thick - half_soft, thick + half_soft, dist);
+    }
+
+    // Optional gaussian falloff outside the core, widening the streak across its direction.
+    if (cross_blur_px > 0.0) {
+        let outside = max(dist - thick, 0.0) / cross_blur_px;
+        width = max(width, exp(-outside * outside));
+    }
 
     // Animate along direction.
     let phase = fract((v / period) + t * speed * 0.25 + r);
 
     // Tail shape (can be quantized for crunchy retro steps).
-    var tail = exp(-phase * 6.0);
+    var tail = exp(-phase * 6.0 * max(tail_fade, 0.0));
+    // Optional fade-in over the first sixteenth (pow(0, 0) is undefined, so skip it at 0).
+    if (head_fade > 0.0) {
+        tail *= pow(min(phase * 16.0, 1.0), head_fade);
+    }
     if (tail_quant_steps >= 2.0) {
         let steps = tail_quant_steps;
         tail = floor(tail * steps) / steps;
     }
 
-    return clamp(line_active * width * tail, 0.0, 1.0);
+    // Per-streak shimmer: a hash per line, re-rolled ~18 times a second and interpolated.
+    var sparkle = 1.0;
+    if (sparkle_amount > 0.0) {
+        let tw = t * 18.0;
+        let k = floor(tw);
+        let s0 = hash11(line_id * 7.31 + k * 1.91);
+        let s1 = hash11(line_id * 7.31 + (k + 1.0) * 1.91);
+        let n = mix(s0, s1, fract(tw));
+        sparkle = max(1.0 + clamp(sparkle_amount, 0.0, 1.0) * (n * 2.0 - 1.0), 0.0);
+    }
+
+    return clamp(line_active * width * tail * sparkle, 0.0, 1.0);
+}
+
+#ifdef MOBILE
+// Cheap stand-in for `lens_rain_mask`: one value-noise lookup, stretched along the fall
+// direction and scrolled with time, instead of the per-line hashing and tail shaping.
+fn lens_rain_mask_cheap(
+    uv: vec2<f32>,
+    dims: vec2<f32>,
+    dir: vec2<f32>,
+    t: f32,
+    density: f32,
+    speed: f32,
+    pattern_scale: f32,
+    seed: f32,
+    spacing_scale: f32,
+) -> f32 {
+    let p = uv * dims * max(pattern_scale, 0.001);
+    let perp = vec2<f32>(-dir.y, dir.x);
+    let q = vec2<f32>(dot(p, perp) / (7.0 * max(spacing_scale, 0.01)) + seed * 101.0, dot(p, dir) / 46.0 - t * speed * 0.25);
+    let n = value_noise(q);
+    return smoothstep(1.0 - clamp(density, 0.0, 1.0), 1.0, n);
+}
+#endif
+
+#ifdef PATTERN_DROPS
+// `RainGlarePattern::Drops`: one drop per active grid cell, sliding down its cell over its
+// lifetime and re-placed across it for the next one. Same frame as `lens_rain_mask`.
+fn drop_rain_mask(
+    uv: vec2<f32>,
+    dims: vec2<f32>,
+    dir: vec2<f32>,
+    t: f32,
+    density: f32,
+    speed: f32,
+    pattern_scale: f32,
+    thickness_px: f32,
+    edge_softness_px: f32,
+    seed: f32,
+    spacing_scale: f32,
+) -> f32 {
+    let p = uv * dims;
+    let perp = vec2<f32>(-dir.y, dir.x);
+    let q = vec2<f32>(dot(p, perp), dot(p, dir));
+
+    let s = max(pattern_scale, 0.001);
+    let cell = 23.0 * max(spacing_scale, 0.01) / s;
+    let cell_id = floor(q / cell) + vec2<f32>(seed * 101.0, seed * 57.0);
+    let local = fract(q / cell) * cell;
+
+    let r = hash12(cell_id);
+    let has_drop = step(r, clamp(density, 0.0, 1.0));
+
+    // Drops slide much slower than streaks fall.
+    let life = t * speed * 0.05 + r;
+    let phase = fract(life);
+    let x = (0.25 + 0.5 * hash12(cell_id + vec2<f32>(floor(life) * 3.7, 1.3))) * cell;
+    let d = local - vec2<f32>(x, mix(0.2, 0.8, phase) * cell);
+    let dist = length(d);
+
+    // Hard-edged disc unless softened, like the streak lines.
+    let radius = min(max(thickness_px * 2.0, 0.5), cell * 0.2);
+    var drop = 1.0 - step(radius, dist);
+#ifdef ANALYTIC_AA
+    drop = clamp((radius - dist) / aa_footprint(q.x) + 0.5, 0.0, 1.0);
+#endif
+    if (edge_softness_px > 0.0) {
+        let half_soft = edge_softness_px * 0.5;
+        drop = 1.0 - smoothstep(radius - half_soft, radius + half_soft, dist);
+    }
+
+    // Fainter, narrower trail up the cell behind the drop.
+    let behind = -d.y;
+    let trail = (1.0 - step(radius * 0.4, abs(d.x))) * step(0.0, behind)
+        * exp(-behind / max(cell * 0.15, 1.0)) * 0.5;
+
+    // Fade each drop in and out so re-placing it doesn't pop.
+    let fade = sin(phase * 3.14159265);
+    return clamp(has_drop * max(drop, trail) * fade, 0.0, 1.0);
+}
+#endif
+
+// Compresses the part of the brightest channel above 1.0, scaling all channels together so
+// the hue survives instead of clipping to white.
+fn rolloff_core(c: vec3<f32>, rolloff: f32) -> vec3<f32> {
+    let peak = max(c.r, max(c.g, c.b));
+    if (rolloff <= 0.0 || peak <= 1.0) {
+        return c;
+    }
+
+    let excess = peak - 1.0;
+    let new_peak = 1.0 + excess / (1.0 + rolloff * excess);
+    return c * (new_peak / peak);
+}
+
+// Resolution the procedural pattern is laid out at; see `RainGlareCoordSpace`.
+fn pattern_dims(dims: vec2<f32>) -> vec2<f32> {
+#ifdef PATTERN_UV_SPACE
+    // Fixed 1080-high reference, so the pattern doesn't move when the window is resized.
+    return dims * (1080.0 / max(dims.y, 1.0));
+#else
+    return dims;
+#endif
+}
+
+// `uv` relative to the camera's viewport, so each eye of a stereo pair that shares one
+// target lays the pattern out over its own half.
+fn viewport_uv(uv: vec2<f32>) -> vec2<f32> {
+    return (uv - settings.viewport.xy) / max(settings.viewport.zw, vec2<f32>(1e-5));
+}
+
+// Size of the camera's viewport at `dims` for the whole target.
+fn viewport_dims(dims: vec2<f32>) -> vec2<f32> {
+    return dims * settings.viewport.zw;
 }
 
 fn snap_uv_to_pixel_center(uv: vec2<f32>, dims: vec2<f32>) -> vec2<f32> {
@@ -88,60 +311,264 @@ fn snap_uv_to_pixel_center(uv: vec2<f32>, dims: vec2<f32>) -> vec2<f32> {
     return px / dims;
 }
 
-@fragment
-fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
-    let base = textureSample(screen_texture, screen_sampler, in.uv);
+// Resolution the effect is evaluated at: the virtual resolution if set, else the native one.
+fn effect_dims(native_dims: vec2<f32>) -> vec2<f32> {
+    let virtual_on = settings.virtual_resolution.x > 0.0 && settings.virtual_resolution.y > 0.0;
+    return select(native_dims, settings.virtual_resolution, virtual_on);
+}
 
-    let dims_u = textureDimensions(screen_texture);
-    let dims = vec2<f32>(f32(dims_u.x), f32(dims_u.y));
+// With a virtual resolution, evaluate the effect once per virtual pixel at its center: the
+// same as rendering it at that size and nearest-upscaling.
+fn effect_uv(uv: vec2<f32>, dims: vec2<f32>) -> vec2<f32> {
+    let virtual_on = settings.virtual_resolution.x > 0.0 && settings.virtual_resolution.y > 0.0;
+    return select(uv, snap_uv_to_pixel_center(uv, dims), virtual_on);
+}
 
+// Screen-space fall direction: the wind, rotated so that (0, 1) maps onto the screen-space
+// direction of world down (camera roll).
+fn fall_dir() -> vec2<f32> {
     let wind_len = length(settings.wind);
-    let dir = select(vec2<f32>(0.0, 1.0), settings.wind / wind_len, wind_len > 1e-5);
+    let wind_dir = select(vec2<f32>(0.0, 1.0), settings.wind / wind_len, wind_len > 1e-5);
+
+    let g_len = length(settings.gravity_screen_dir);
+    let g = select(vec2<f32>(0.0, 1.0), settings.gravity_screen_dir / g_len, g_len > 1e-5);
+    return vec2<f32>(g.y * wind_dir.x + g.x * wind_dir.y, -g.x * wind_dir.x + g.y * wind_dir.y);
+}
 
-    let rain = lens_rain_mask(
-        in.uv, dims, dir,
+// The procedural lens streak mask at effect-space `uv`, before dry patches.
+fn streak_mask(uv: vec2<f32>, dims: vec2<f32>) -> f32 {
+    let dir = fall_dir();
+    let view_uv = viewport_uv(uv);
+    let pattern_size = pattern_dims(viewport_dims(dims));
+
+    // Zooming in enlarges the pattern.
+    let zoom = max(settings.zoom_factor, 1e-3);
+    let pattern_scale = max(settings.pattern_scale, 1e-3) / zoom;
+
+    // The lens layer may be offset from the scene (camera-shake parallax).
+    let lens_uv = view_uv + settings.uv_offset;
+    // Positive bias = denser at the top (uv.y = 0), negative = denser at the bottom.
+    let vertical = max(1.0 + settings.vertical_bias * (1.0 - 2.0 * view_uv.y), 0.0);
+    let density = settings.rain_density * settings.rain_amount * vertical * max(settings.streak_count_scale, 0.0);
+    // One mask function per `RainGlarePattern`.
+#ifdef PATTERN_DROPS
+    return drop_rain_mask(
+        lens_uv, pattern_size, dir,
         settings.time,
-        settings.rain_density,
+        density,
         settings.speed,
-        settings.pattern_scale,
+        pattern_scale,
+        settings.mask_thickness_px,
+        settings.edge_softness_px,
+        settings.seed,
+        settings.streak_spacing,
+    );
+#else ifdef MOBILE
+    return lens_rain_mask_cheap(
+        lens_uv, pattern_size, dir,
+        settings.time,
+        density,
+        settings.speed,
+        pattern_scale,
+        settings.seed,
+        settings.streak_spacing,
+    );
+#else
+    return lens_rain_mask(
+        lens_uv, pattern_size, dir,
+        settings.time,
+        density,
+        settings.speed,
+        pattern_scale,
         settings.mask_thickness_px,
         settings.tail_quant_steps,
+        settings.edge_softness_px,
+        settings.sparkle_amount,
+        settings.head_fade,
+        settings.tail_fade,
+        settings.seed,
+        settings.cross_blur_px,
+        settings.streak_spacing,
     );
+#endif
+}
+
+// The streak mask at effect-space `uv`: nearest-sampled from the prepass mask when this
+// view has one (`COMPUTE_MASK`, or a mask at the virtual resolution), else evaluated here.
+fn effect_mask(uv: vec2<f32>, dims: vec2<f32>) -> f32 {
+    let mask_dims = textureDimensions(mask_texture);
+#ifndef COMPUTE_MASK
+    if (any(vec2<f32>(mask_dims) != dims)) {
+        return streak_mask(uv, dims);
+    }
+#endif
+    let px = min(vec2<u32>(uv * vec2<f32>(mask_dims)), mask_dims - vec2<u32>(1u));
+    return textureLoad(mask_texture, px, 0).r;
+}
 
+#ifdef MASK_PREPASS
+// Mask prepass: evaluates `streak_mask` once per texel of `mask_out`, exactly as `fragment`
+// would at that pixel's center; native pixels for `RainGlareCompute`, virtual ones for a
+// virtual resolution.
+@compute @workgroup_size(8, 8, 1)
+fn compute_mask(@builtin(global_invocation_id) id: vec3<u32>) {
+    let native_u = textureDimensions(mask_out);
+    if (any(id.xy >= native_u)) {
+        return;
+    }
+
+    let native_dims = vec2<f32>(f32(native_u.x), f32(native_u.y));
+    let dims = effect_dims(native_dims);
+    let uv = effect_uv((vec2<f32>(id.xy) + 0.5) / native_dims, dims);
+    textureStore(mask_out, id.xy, vec4<f32>(streak_mask(uv, dims), 0.0, 0.0, 0.0));
+}
+#endif
+
+@fragment
+#ifdef WETNESS
+fn fragment(in: FullscreenVertexOutput) -> RainGlareOutput {
+#else
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+#endif
+    let base = textureSample(screen_texture, screen_sampler, in.uv);
+
+    let dims_u = textureDimensions(screen_texture);
+    let native_dims = vec2<f32>(f32(dims_u.x), f32(dims_u.y));
+
+    // `base` stays native; see `effect_uv`.
+    let dims = effect_dims(native_dims);
+    let uv = effect_uv(in.uv, dims);
+    let dir = fall_dir();
+
+    let exclusion = textureSample(exclusion_mask, screen_sampler, uv).a;
+    let pattern_size = pattern_dims(viewport_dims(dims));
+
+    let depth_dims = textureDimensions(depth_texture);
+    let depth_px = min(vec2<u32>(uv * vec2<f32>(depth_dims)), depth_dims - vec2<u32>(1u));
+    let depth = textureLoad(depth_texture, depth_px, 0).r;
+    let sky = select(0.0, 1.0, depth <= 0.0);
+
+    // Optionally bend the streak trail down the surface under the pixel: world down projected
+    // onto the surface plane, then onto the screen like `gravity_screen_dir`. The lens mask
+    // keeps the screen-space direction.
+    let normal_dims = textureDimensions(normal_texture);
+    let normal_px = min(vec2<u32>(uv * vec2<f32>(normal_dims)), normal_dims - vec2<u32>(1u));
+    let normal_raw = textureLoad(normal_texture, normal_px, 0);
+    let n = normal_raw.rgb * 2.0 - 1.0;
+    let down = vec3<f32>(0.0, -1.0, 0.0);
+    let tangent = down - n * dot(down, n);
+    let surface_dir = vec2<f32>(dot(tangent, settings.camera_right), -dot(tangent, settings.camera_up));
+    let surface_len = length(surface_dir);
+    // Fade out where the surface faces the camera and its downhill direction degenerates.
+    let follow = clamp(settings.normal_follow, 0.0, 1.0) * normal_raw.a * smoothstep(0.0, 0.2, surface_len);
+    let bent = mix(dir, surface_dir / max(surface_len, 1e-5), follow);
+    let streak_dir = select(dir, normalize(bent), length(bent) > 1e-5);
+
+    // Zooming in lengthens the streaks along with the pattern (see `streak_mask`).
+    let zoom = max(settings.zoom_factor, 1e-3);
+
+    let lens_uv = viewport_uv(uv) + settings.uv_offset;
+    let rain_raw = effect_mask(uv, dims);
+    let wet = dry_patch_mask(lens_uv, pattern_size, settings.dry_patch_scale, settings.dry_patch_amount, settings.seed);
+    let rain = rain_raw * wet;
+
+#ifdef WETNESS
+    // Dry last frame's wetness a little and add this frame's streaks on top.
+    let wetness = max(textureSample(wetness_texture, screen_sampler, in.uv).r * settings.wetness_decay, rain);
+    let wet_boost = 1.0 + max(settings.wetness_gain, 0.0) * wetness;
+#else
+    let wet_boost = 1.0;
+#endif
+
+#ifdef MOBILE
+    let samples: i32 = 4;
+#else
     let samples: i32 = 16;
-    let len_uv = settings.streak_length_px / max(dims.y, 1.0);
-    let step_uv = dir * (len_uv / f32(samples));
+#endif
+    // Bright sources may reach past the base length; sample the extended span and cut each
+    // sample off at its own reach below.
+    let length_boost = max(settings.length_from_brightness, 0.0);
+    let reach_scale = 1.0 + length_boost;
+    let len_uv = settings.streak_length_px * zoom * reach_scale / max(dims.y, 1.0);
+    let step_uv = streak_dir * (len_uv / f32(samples));
+
+    // 0..1 blend between free and pixel-snapped sampling.
+    let snap = clamp(settings.snap_to_pixel, 0.0, 1.0);
 
-    // Disable jitter when snapping (keeps the retro edges clean).
-    let jitter_mask = 1.0 - step(0.5, settings.snap_to_pixel);
-    let jitter = (hash12(in.uv * dims + vec2<f32>(settings.time, settings.time * 1.37)) - 0.5) * 0.9 * jitter_mask;
+    // Fade out jitter as snapping takes over (keeps the retro edges clean).
+    let jitter_mask = 1.0 - snap;
+    let jitter = (hash12(uv * dims + vec2<f32>(settings.time + settings.seed, settings.time * 1.37)) - 0.5) * 0.9 * jitter_mask;
 
-    let uv0 = in.uv + vec2<f32>(jitter / dims.x, 0.0);
+    let uv0 = uv + vec2<f32>(jitter / dims.x, 0.0);
+
+    // Over moving pixels, stretch the sample trail back along the screen motion.
+    let smear = textureSample(motion_vectors, screen_sampler, uv).rg * settings.motion_response;
 
     var accum = vec3<f32>(0.0);
     var wsum = 0.0;
 
     for (var i: i32 = 0; i < samples; i = i + 1) {
         let fi = f32(i);
-        let suv = uv0 - step_uv * (fi + jitter);
+        let suv = uv0 - step_uv * (fi + jitter) + smear * (fi / f32(samples));
 
-        let use_snap = settings.snap_to_pixel >= 0.5;
-        let uv_s = select(suv, snap_uv_to_pixel_center(suv, dims), use_snap);
+        let uv_s = mix(suv, snap_uv_to_pixel_center(suv, dims), snap);
 
         let c = textureSample(screen_texture, screen_sampler, uv_s).rgb;
 
         // Bright-pass weight
-        let b = clamp((luma(c) - settings.threshold) / max(1.0 - settings.threshold, 1e-5), 0.0, 1.0);
-
-        let w = b * exp(-fi * 0.16);
-        accum += c * w;
+        let b = clamp((bright_pass_luma(c, uv_s, dims) - settings.threshold) / max(1.0 - settings.threshold, 1e-5), 0.0, 1.0);
+
+        // Distance in units of the base streak length's sample spacing.
+        let d = fi * reach_scale;
+        var reach = 1.0;
+        if (length_boost > 0.0) {
+            reach = clamp((1.0 + length_boost * b) * f32(samples) - d, 0.0, 1.0);
+        }
+
+        // Falloff per base sample spacing, so fewer samples cover the same streak profile.
+        let w = b * reach * exp(-d * 0.16 * 16.0 / f32(samples));
+        // Samples further up the trail light this pixel with their streak's tail.
+        let gradient = mix(settings.head_color, settings.tail_color, fi / f32(samples));
+        accum += c * gradient * w;
         wsum += w;
     }
 
-    let streak = accum / max(wsum, 1e-5);
+    // Gamma > 1 compresses bright highlights, < 1 expands them.
+    let gamma = max(settings.brightness_gamma, 1e-3);
+    let shaped = pow(accum / max(wsum, 1e-5), vec3<f32>(1.0 / gamma));
+    let streak = rolloff_core(shaped, settings.core_rolloff) * settings.daylight_tint;
 //    let out_rgb = base.rgb + streak * (settings.intensity * rain);
     let angle_fade = settings.view_angle_factor;
-    let out_rgb = base.rgb + streak * (settings.intensity * rain * angle_fade);
-
-    return vec4<f32>(out_rgb, base.a);
+    let keep = (1.0 - clamp(exclusion, 0.0, 1.0)) * mix(1.0, sky, clamp(settings.sky_only, 0.0, 1.0));
+    let intensity = settings.intensity * settings.exposure_compensation * settings.rain_amount
+        * settings.outdoor_amount * settings.daylight_intensity
+        * (1.0 + settings.beat_response * settings.beat);
+    // Over bright backgrounds the streaks also dim what they cover, so they still read
+    // as streaks where adding light alone would wash out. `bright` stays near 0 in the dark.
+    let base_luma = max(luma(base.rgb), 0.0);
+    let bright = base_luma / (1.0 + base_luma);
+    let darken = clamp(settings.contrast_preserve, 0.0, 1.0) * bright * clamp(rain * angle_fade * keep, 0.0, 1.0);
+    let out_rgb = base.rgb * (1.0 - darken) + streak * (intensity * rain * wet_boost * angle_fade * keep);
+
+#ifdef DEBUG_MASK_ONLY
+    let color = vec4<f32>(vec3<f32>(rain * keep), 1.0);
+#else ifdef DEBUG_STREAKS_ONLY
+    let color = vec4<f32>(out_rgb - base.rgb, 1.0);
+#else ifdef ALPHA_PREMULTIPLIED
+    // Keep rgb <= alpha: where the streaks outshine the existing alpha they become coverage.
+    let peak = max(out_rgb.r, max(out_rgb.g, out_rgb.b));
+    let color = vec4<f32>(out_rgb, max(base.a, min(peak, 1.0)));
+#else ifdef ALPHA_COVERAGE
+    // Straight alpha: rgb * a is exactly the light the streaks add on top of the scene.
+    let coverage = clamp(rain * angle_fade * keep, 0.0, 1.0);
+    let color = vec4<f32>(streak * (intensity * wet_boost), coverage);
+#else
+    let color = vec4<f32>(out_rgb, base.a);
+#endif
+
+#ifdef WETNESS
+    return RainGlareOutput(color, vec4<f32>(wetness, 0.0, 0.0, 1.0));
+#else
+    return color;
+#endif
 }
@@ -0,0 +1,16 @@
+// Gathers a READBACK_GRID x READBACK_GRID grid of texel centres from the view into one half
+// of the readback target, which is then copied out in one go. The target is `Rgba16Float`,
+// so HDR values survive and sRGB views arrive linear.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+
+const GRID: u32 = #{READBACK_GRID}u;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Both halves are drawn through a viewport, so the grid cell repeats every GRID rows.
+    let cell = vec2<u32>(in.position.xy) % GRID;
+    let dims = textureDimensions(source);
+    return textureLoad(source, (2u * cell + 1u) * dims / (2u * GRID), 0);
+}
@@ -0,0 +1,78 @@
+// Uniform layout of a rain camera's settings, shared with custom shaders (e.g. wet-surface
+// materials) through `#import rain_glare::types::RainGlareSettings`. Mirrors the Rust
+// `RainGlareUniform` field for field; the byte offsets are listed next to
+// `SETTINGS_UNIFORM_SIZE` in lib.rs.
+#define_import_path rain_glare::types
+
+// The order is the layout and only ever grows at the end, so related fields are grouped
+// where they sit. `derived` marks values the plugin computes (`RainGlareDerived`) instead of
+// authored settings.
+struct RainGlareSettings {
+    // Look and animation.
+    intensity: f32,
+    threshold: f32,
+    streak_length_px: f32,
+    rain_density: f32,
+    wind: vec2<f32>,
+    speed: f32,
+    time: f32,
+
+    // Pattern.
+    pattern_scale: f32,
+    mask_thickness_px: f32,
+    snap_to_pixel: f32,
+    tail_quant_steps: f32,
+    view_angle_factor: f32, // derived
+    edge_softness_px: f32,
+
+    // Brightness response and dry patches.
+    threshold_color_weights: vec3<f32>,
+    brightness_gamma: f32,
+    dry_patch_scale: f32,
+    dry_patch_amount: f32,
+    exposure_compensation: f32, // derived
+
+    // Streak shape.
+    core_rolloff: f32,
+    sparkle_amount: f32,
+    length_from_brightness: f32,
+    gravity_screen_dir: vec2<f32>, // derived
+    sky_only: f32,
+    rain_amount: f32, // derived
+    head_fade: f32,
+    tail_fade: f32,
+    uv_offset: vec2<f32>,
+    highlight_bleed_px: f32,
+    motion_response: f32,
+
+    // Camera and scene response.
+    outdoor_amount: f32, // derived
+    virtual_resolution: vec2<f32>, // derived
+    beat_response: f32,
+    beat: f32, // derived
+    normal_follow: f32,
+    camera_right: vec3<f32>, // derived
+    camera_up: vec3<f32>, // derived
+    seed: f32,
+    zoom_factor: f32, // derived
+    cross_blur_px: f32,
+    vertical_bias: f32,
+
+    // Wetness.
+    dry_rate: f32,
+    wetness_gain: f32,
+    wetness_decay: f32, // derived
+
+    // Streak spacing.
+    streak_spacing: f32,
+    streak_count_scale: f32,
+
+    // Colour.
+    daylight_tint: vec3<f32>, // derived
+    daylight_intensity: f32, // derived
+    contrast_preserve: f32,
+    head_color: vec3<f32>,
+    tail_color: vec3<f32>,
+
+    viewport: vec4<f32>, // derived
+};
@@ -0,0 +1,229 @@
+// Sweeps `rain_density` across the quality modes and prints the average, min and max frame
+// time of each combination, then exits.
+//
+//   cargo run --release --example bench -- [--headless] [--frames N]
+//
+// `--headless` renders into an offscreen image without opening a window. `--frames` sets
+// how many frames are measured per combination (default 240), after a warm-up that also
+// covers recompiling the pipelines when the mode changes.
+use std::time::Duration;
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    },
+    window::{ExitCondition, PresentMode},
+    winit::WinitPlugin,
+};
+use rain_glare::{
+    RainGlareCameraBundle, RainGlareCompute, RainGlarePlugin, RainGlareQuality, RainGlareSettings,
+};
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+const WARMUP_FRAMES: u32 = 60;
+const DENSITIES: [f32; 4] = [0.1, 0.35, 0.7, 1.0];
+
+#[derive(Clone, Copy, Debug)]
+enum Mode {
+    Full,
+    Mobile,
+    Compute,
+}
+
+const MODES: [Mode; 3] = [Mode::Full, Mode::Mobile, Mode::Compute];
+
+#[derive(Resource)]
+struct Bench {
+    headless: bool,
+    frames: u32,
+    step: usize,
+    frame: u32,
+    sum_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl Bench {
+    fn current(&self) -> Option<(Mode, f32)> {
+        let mode = MODES.get(self.step / DENSITIES.len())?;
+        Some((*mode, DENSITIES[self.step % DENSITIES.len()]))
+    }
+
+    fn next(&mut self) {
+        self.step += 1;
+        self.frame = 0;
+        self.sum_ms = 0.0;
+        self.min_ms = f64::MAX;
+        self.max_ms = 0.0;
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let headless = args.iter().any(|arg| arg == "--headless");
+    let frames = args
+        .iter()
+        .position(|arg| arg == "--frames")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(240);
+
+    let mut app = App::new();
+    if headless {
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .disable::<WinitPlugin>(),
+            ScheduleRunnerPlugin::run_loop(Duration::ZERO),
+        ));
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                title: "rain glare bench".into(),
+                resolution: (WIDTH as f32, HEIGHT as f32).into(),
+                present_mode: PresentMode::AutoNoVsync,
+                ..default()
+            }),
+            ..default()
+        }));
+    }
+
+    app.add_plugins((FrameTimeDiagnosticsPlugin, RainGlarePlugin::default()))
+        .insert_resource(Bench {
+            headless,
+            frames: frames.max(1),
+            step: 0,
+            frame: 0,
+            sum_ms: 0.0,
+            min_ms: f64::MAX,
+            max_ms: 0.0,
+        })
+        .add_systems(Startup, setup_scene)
+        .add_systems(Update, run_bench)
+        .run();
+}
+
+fn setup_scene(
+    mut commands: Commands,
+    bench: Res<Bench>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut camera = RainGlareCameraBundle::from_transform(
+        Transform::from_xyz(0.0, 2.5, 9.0).looking_at(Vec3::new(0.0, 1.0, 0.0), Vec3::Y),
+    );
+    if bench.headless {
+        let mut target = Image::new_fill(
+            Extent3d {
+                width: WIDTH,
+                height: HEIGHT,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        target.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_DST
+            | TextureUsages::RENDER_ATTACHMENT;
+        camera.camera.camera.target = RenderTarget::Image(images.add(target));
+    }
+    commands.spawn(camera);
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Plane3d::default().mesh().size(20.0, 20.0)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.02, 0.04, 0.08),
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+        ..default()
+    });
+
+    // A row of bright sources so every sample of the bright-pass has work to do.
+    let sphere_mesh = meshes.add(Sphere::new(0.4).mesh().ico(4).unwrap());
+    for i in 0..9 {
+        let x = (i as f32 - 4.0) * 1.2;
+        commands.spawn(PbrBundle {
+            mesh: sphere_mesh.clone(),
+            material: materials.add(StandardMaterial {
+                emissive: LinearRgba::rgb(10.0, 6.0 + (i % 3) as f32 * 2.0, 2.0 + i as f32),
+                ..default()
+            }),
+            transform: Transform::from_xyz(x, 1.2, 0.0),
+            ..default()
+        });
+    }
+
+    println!("mode      density   avg ms   min ms   max ms");
+}
+
+fn run_bench(
+    mut commands: Commands,
+    mut bench: ResMut<Bench>,
+    diagnostics: Res<DiagnosticsStore>,
+    mut quality: ResMut<RainGlareQuality>,
+    mut cameras: Query<&mut RainGlareSettings>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some((mode, density)) = bench.current() else {
+        return;
+    };
+
+    if bench.frame == 0 {
+        // Uncapped density in every mode, so the sweep means the same thing throughout.
+        *quality = RainGlareQuality {
+            mobile: matches!(mode, Mode::Mobile),
+            ..RainGlareQuality::HIGH
+        };
+        match mode {
+            Mode::Compute => commands.insert_resource(RainGlareCompute),
+            Mode::Full | Mode::Mobile => commands.remove_resource::<RainGlareCompute>(),
+        }
+        for mut settings in &mut cameras {
+            settings.rain_density = density;
+        }
+    }
+
+    bench.frame += 1;
+    if bench.frame <= WARMUP_FRAMES {
+        return;
+    }
+
+    let Some(ms) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.value())
+    else {
+        return;
+    };
+    bench.sum_ms += ms;
+    bench.min_ms = bench.min_ms.min(ms);
+    bench.max_ms = bench.max_ms.max(ms);
+
+    if bench.frame == WARMUP_FRAMES + bench.frames {
+        println!(
+            "{:<9} {:>7.2} {:>8.3} {:>8.3} {:>8.3}",
+            format!("{mode:?}"),
+            density,
+            bench.sum_ms / bench.frames as f64,
+            bench.min_ms,
+            bench.max_ms
+        );
+        bench.next();
+        if bench.current().is_none() {
+            exit.send(AppExit::Success);
+        }
+    }
+}
@@ -2,19 +2,23 @@ use bevy::{
     core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
     prelude::*,
 };
-use rain_glare::{RainGlarePlugin, RainGlareSettings};
+use rain_glare::{RainGlareDebugView, RainGlarePlugin, RainGlareSettings};
 
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(RainGlarePlugin)
+        .add_plugins(RainGlarePlugin::default())
         .add_systems(Startup, setup_scene)
         .add_systems(
             Update,
             (
                 spin_highlights,
                 tweak_rain_glare_settings,
-                update_hud_text.after(tweak_rain_glare_settings),
+                cycle_debug_view,
+                print_settings_literal,
+                update_hud_text
+                    .after(tweak_rain_glare_settings)
+                    .after(cycle_debug_view),
             ),
         )
         .run();
@@ -258,7 +262,29 @@ fn tweak_rain_glare_settings(
     }
 }
 
-fn update_hud_text(settings: Query<&RainGlareSettings>, mut text: Query<&mut Text, With<HudText>>) {
+fn cycle_debug_view(keys: Res<ButtonInput<KeyCode>>, mut debug_view: ResMut<RainGlareDebugView>) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        *debug_view = match *debug_view {
+            RainGlareDebugView::Off => RainGlareDebugView::MaskOnly,
+            RainGlareDebugView::MaskOnly => RainGlareDebugView::StreaksOnly,
+            RainGlareDebugView::StreaksOnly => RainGlareDebugView::Off,
+        };
+    }
+}
+
+fn print_settings_literal(keys: Res<ButtonInput<KeyCode>>, settings: Query<&RainGlareSettings>) {
+    if keys.just_pressed(KeyCode::KeyP) {
+        for s in &settings {
+            info!("current rain glare settings:\n{}", s.to_rust_literal());
+        }
+    }
+}
+
+fn update_hud_text(
+    settings: Query<&RainGlareSettings>,
+    debug_view: Res<RainGlareDebugView>,
+    mut text: Query<&mut Text, With<HudText>>,
+) {
     let Ok(s) = settings.get_single() else {
         return;
     };
@@ -276,7 +302,16 @@ R/F density     {:.2}
 T/G wind.x      {:.2}
 Y/H wind.y      {:.2}
 U/J speed       {:.2}
+M   debug view  {:?}
+P   log settings as Rust
 ",
-        s.intensity, s.threshold, s.streak_length_px, s.rain_density, s.wind.x, s.wind.y, s.speed
+        s.intensity,
+        s.threshold,
+        s.streak_length_px,
+        s.rain_density,
+        s.wind.x,
+        s.wind.y,
+        s.speed,
+        *debug_view
     );
 }
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use rain_glare::{
+    RainGlareCameraBundle, RainGlarePlugin, RainGlareSettings, RainGlareTransition,
+    RainGlareTransitionFinished,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(RainGlarePlugin::default())
+        .add_systems(Startup, setup_scene)
+        .add_systems(Update, (start_transition, log_finished_transitions))
+        .run();
+}
+
+fn setup_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(RainGlareCameraBundle {
+        settings: RainGlareSettings::LIGHT_DRIZZLE,
+        ..RainGlareCameraBundle::from_transform(
+            Transform::from_xyz(0.0, 2.5, 9.0).looking_at(Vec3::new(0.0, 1.0, 0.0), Vec3::Y),
+        )
+    });
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Plane3d::default().mesh().size(20.0, 20.0)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.02, 0.04, 0.08),
+            perceptual_roughness: 0.8,
+            ..default()
+        }),
+        ..default()
+    });
+
+    let sphere_mesh = meshes.add(Sphere::new(0.6).mesh().ico(5).unwrap());
+    for (x, emissive) in [
+        (-2.5, LinearRgba::rgb(10.0, 6.0, 1.4)),
+        (0.0, LinearRgba::rgb(3.5, 10.0, 10.0)),
+        (2.5, LinearRgba::rgb(10.0, 2.0, 8.0)),
+    ] {
+        commands.spawn(PbrBundle {
+            mesh: sphere_mesh.clone(),
+            material: materials.add(StandardMaterial {
+                emissive,
+                ..default()
+            }),
+            transform: Transform::from_xyz(x, 1.2, 0.0),
+            ..default()
+        });
+    }
+
+    commands.spawn(
+        TextBundle::from_section(
+            "Space: drizzle -> downpour | Backspace: downpour -> drizzle",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+    );
+}
+
+fn start_transition(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    cameras: Query<Entity, With<RainGlareSettings>>,
+) {
+    let target = if keys.just_pressed(KeyCode::Space) {
+        RainGlareSettings::DOWNPOUR
+    } else if keys.just_pressed(KeyCode::Backspace) {
+        RainGlareSettings::LIGHT_DRIZZLE
+    } else {
+        return;
+    };
+
+    for camera in &cameras {
+        // Replacing a running transition restarts it from the current settings.
+        commands
+            .entity(camera)
+            .insert(RainGlareTransition::new(target, 4.0).with_event());
+    }
+}
+
+fn log_finished_transitions(mut finished: EventReader<RainGlareTransitionFinished>) {
+    for event in finished.read() {
+        info!("rain transition finished on {:?}", event.entity);
+    }
+}
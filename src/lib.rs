@@ -6,38 +6,78 @@
 use bevy::{
     asset::load_internal_asset,
     core_pipeline::{
+        bloom::BloomSettings,
         core_3d::graph::{Core3d, Node3d},
         fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        motion_blur::MotionBlur,
+        prepass::{DepthPrepass, ViewPrepassTextures},
+        tonemapping::Tonemapping,
+    },
+    diagnostic::{DiagnosticPath, DiagnosticsStore},
+    ecs::{
+        component::Tick,
+        query::{QueryData, QueryItem},
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+        system::{EntityCommands, SystemChangeTick, SystemParam},
     },
-    ecs::query::QueryItem,
     prelude::*,
     render::{
-        RenderApp,
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+        camera::{CameraMainTextureUsages, CameraUpdateSystem, ExtractedCamera, Viewport},
+        diagnostic::RecordDiagnostics,
         extract_component::{
             ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
             UniformComponentPlugin,
         },
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::RenderAssets,
         render_graph::{
-            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+            NodeRunError, RenderGraph, RenderGraphApp, RenderGraphContext, RenderLabel,
+            RenderSubGraph, ViewNode, ViewNodeRunner,
         },
         render_resource::{
-            binding_types::{sampler, texture_2d, uniform_buffer},
+            binding_types::{sampler, texture_2d, texture_storage_2d, uniform_buffer},
             *,
         },
-        renderer::{RenderContext, RenderDevice},
-        texture::BevyDefault,
+        renderer::{RenderAdapter, RenderContext, RenderDevice},
+        texture::{BevyDefault, CachedTexture, FallbackImageZero, GpuImage, TextureCache},
         view::ViewTarget,
     },
+    transform::TransformSystem,
+    ui::graph::NodeUi,
     utils::HashMap,
 };
+use half::f16;
+use naga_oil::compose::{Composer, NagaModuleDescriptor, ShaderDefValue};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU8, Ordering},
+};
+use wgpu::DownlevelFlags;
 
 /// Handle for the internally embedded rain glare shader.
 pub const RAIN_GLARE_SHADER_HANDLE: Handle<Shader> =
     Handle::weak_from_u128(0xA6D4_91D1_D6C3_44FD_821D_A4A6_9B0A_9B11);
 
+/// Handle for the embedded `rain_glare::types` shader import, which declares the WGSL
+/// `RainGlareSettings` struct.
+///
+/// Custom shaders (e.g. wet-surface materials) can `#import rain_glare::types::RainGlareSettings`
+/// to read the same parameters, which a material binds as a [`RainGlareUniform`].
+pub const RAIN_GLARE_TYPES_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x3F0B_7E25_9C41_4A8E_B6D2_1C7F_58E0_D493);
+
+/// Handle for the embedded shader gathering the [`RainGlareAutoThreshold`] and
+/// [`RainGlareCoverage`] sample grid.
+const RAIN_GLARE_READBACK_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5C2E_80A7_1B94_4F36_9D0B_E7A4_3F61_C825);
+
 /// Component that enables the rain glare effect on a camera and configures its parameters.
-#[allow(dead_code)]
-#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+///
+/// Values the plugin computes itself (the camera's roll and zoom, the ambient and
+/// time-of-day multipliers, ...) live in the camera's [`RainGlareDerived`] instead.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component, Default)]
 pub struct RainGlareSettings {
     pub intensity: f32,
     pub threshold: f32,
@@ -52,7 +92,7 @@ pub struct RainGlareSettings {
     pub pattern_scale: f32,
     // NEW: hard-edged line thickness in pixels (keep ~0.5..1.25)
     pub mask_thickness_px: f32,
-    // NEW: snap streak sampling to pixel centers (1.0 = on, 0.0 = off)
+    // NEW: snap streak sampling to pixel centers (1.0 = on, 0.0 = off, in-between blends)
     pub snap_to_pixel: f32,
     // NEW: quantize mask intensity steps (0 = off, 8/16 = crunchy)
     pub tail_quant_steps: f32,
@@ -60,239 +100,5843 @@ pub struct RainGlareSettings {
     /// 0..1: how “horizon-facing” the view is.
     /// 1 = looking at horizon, 0 = straight up/down.
     pub view_angle_factor: f32,
+
+    /// Width in pixels of a smoothstep ramp across the streak edge (0 = hard edge).
+    /// Applied after `snap_to_pixel`: snapping picks the sample positions, softening then
+    /// blends the mask edge, so both can be combined for soft-yet-stable streaks.
+    pub edge_softness_px: f32,
+
+    /// Per-channel weights used to compute brightness before thresholding.
+    /// The default is Rec. 709 luminance; e.g. `(1.0, 0.8, 0.2)` favors warm highlights.
+    pub threshold_color_weights: Vec3,
+
+    /// Response curve applied to the streak color before compositing (1 = linear).
+    /// Values > 1 compress very bright highlights so they stop dominating; < 1 expands them.
+    pub brightness_gamma: f32,
+
+    /// Approximate size in pixels of the dry patches carved out of the streak mask.
+    pub dry_patch_scale: f32,
+    /// 0..1: fraction of the glass left dry by low-frequency noise (0 = fully wet).
+    pub dry_patch_amount: f32,
+
+    /// Soft, hue-preserving compression of streak brightness above 1.0 so very bright cores
+    /// don't clip to white (0 = off). Larger values compress harder.
+    pub core_rolloff: f32,
+
+    /// 0..1: per-streak, high-frequency brightness shimmer (0 = steady streaks).
+    pub sparkle_amount: f32,
+
+    /// How much brighter sources extend their streaks: a sample at full bright-pass strength
+    /// reaches `streak_length_px * (1 + length_from_brightness)`. 0 keeps a uniform length.
+    pub length_from_brightness: f32,
+
+    /// 0..1: restricts the glare to background pixels at the far plane (1.0 = sky only,
+    /// 0.0 = everywhere, in-between blends). Needs a [`DepthPrepass`] on the camera and
+    /// `Msaa::Off`; otherwise it has no effect and a warning is logged.
+    pub sky_only: f32,
+
+    /// Exponent of the fade-in at the head of each streak, over its first sixteenth
+    /// (0 = hard head, the original look; 1 = linear ramp; larger = softer).
+    pub head_fade: f32,
+
+    /// Rate of the exponential falloff towards the tail (1 = the original profile; smaller
+    /// values give a longer, softer tail, larger ones a short, sharp one).
+    pub tail_fade: f32,
+
+    /// Screen-UV shift of the streak mask (the lens layer) relative to the scene, e.g. driven
+    /// from camera shake for a parallax lag. The scene and its highlights are sampled as usual.
+    pub uv_offset: Vec2,
+
+    /// Radius in pixels of a small box blur applied to the bright-pass brightness only, so
+    /// sub-pixel highlights still streak (0 = off). Costs four extra taps per streak sample.
+    pub highlight_bleed_px: f32,
+
+    /// How far streaks smear back along the local screen motion, in frames of motion (0 = off).
+    /// Needs a `MotionVectorPrepass` on the camera and `Msaa::Off`; otherwise it has no effect.
+    pub motion_response: f32,
+
+    /// How strongly `intensity` pulses with [`RainGlareBeat`]: the effective intensity is
+    /// scaled by `1 + beat_response * beat` (0 = ignore the beat, the default).
+    pub beat_response: f32,
+
+    /// How far the streak trail bends to run down the scene surface under each pixel (0 = pure
+    /// screen-space rain, the default; 1 = fully along the surface). Needs a `NormalPrepass`
+    /// on the camera and `Msaa::Off`; otherwise it has no effect.
+    pub normal_follow: f32,
+
+    /// Offset mixed into the pattern's hashes: the same `time` and `seed` always give the
+    /// same rain, and different seeds vary it between otherwise identical cameras (0 = the
+    /// original pattern, the default).
+    pub seed: f32,
+
+    /// Width in pixels of a gaussian falloff added outside each streak, softening it across
+    /// its direction like slight defocus (0 = crisp, the default). Unlike `edge_softness_px`
+    /// this widens the streak rather than just smoothing its edge.
+    pub cross_blur_px: f32,
+
+    /// Concentrates rain towards one edge of the screen, like a windshield where drops land
+    /// at the top: density is scaled by `1 + vertical_bias` at the top edge, falling linearly
+    /// to `1 - vertical_bias` at the bottom. Positive = more at the top, negative = more at
+    /// the bottom, 0 = uniform (the default).
+    pub vertical_bias: f32,
+
+    /// How fast the wetness buffer dries, per second: each frame it is multiplied by
+    /// `exp(-dry_rate * dt)`. Only used while [`RainGlareWetness`] is enabled.
+    pub dry_rate: f32,
+
+    /// How much brighter streaks get where wetness has accumulated: the streak term is
+    /// scaled by `1 + wetness_gain * wetness`. Only used while [`RainGlareWetness`] is enabled.
+    pub wetness_gain: f32,
+
+    /// Distance between the candidate streak lines, as a multiple of the base spacing set by
+    /// `pattern_scale` (1 = the original look). Larger values space the streaks further
+    /// apart without changing how many of the lines carry one.
+    pub streak_spacing: f32,
+
+    /// Multiplier on the fraction of candidate lines that carry a streak, which is
+    /// `rain_density * streak_count_scale` (clamped to 1). Combine with `streak_spacing`
+    /// for sparse-but-long or dense-but-short rain; 1 keeps `rain_density` as the only knob.
+    pub streak_count_scale: f32,
+
+    /// How much the streaks darken bright backgrounds underneath them, 0..1. Additive streaks
+    /// wash out over a bright sky; with this above zero the rain also dims what it covers in
+    /// proportion to the background's brightness, so it stays visible as a contrast edge.
+    /// Dark backgrounds are left alone, and 0 keeps the purely additive look.
+    pub contrast_preserve: f32,
+
+    /// Color multiplier at the head of each streak, next to the light it comes from,
+    /// blending into `tail_color` along the streak's length. sRGB like
+    /// [`RainGlareDaylight::tint`]; converted to linear before it reaches the shader. Alpha is
+    /// ignored. White (the default for both) keeps the sampled scene color.
+    pub head_color: Srgba,
+
+    /// Color multiplier at the far end of each streak; see `head_color`.
+    pub tail_color: Srgba,
 }
 
 impl Default for RainGlareSettings {
     fn default() -> Self {
-        Self {
-            intensity: 0.35,
-            threshold: 0.65,
-            streak_length_px: 96.0,
-            rain_density: 0.55,
-            wind: Vec2::new(0.10, 1.0),
-            speed: 1.2,
-            time: 0.0,
+        Self::DEFAULT
+    }
+}
 
-            pattern_scale: 3.0,
-            mask_thickness_px: 0.75,
-            snap_to_pixel: 1.0,
-            tail_quant_steps: 8.0,
-            
-            view_angle_factor: 1.0,
-        }
+/// Values the plugin derives for a rain camera every frame, from the camera itself and from
+/// resources such as [`RainGlareExposureCompensation`], [`RainGlareRainAmount`] and
+/// [`RainGlareTimeOfDay`]. They reach the shader next to the camera's [`RainGlareSettings`],
+/// as part of its [`RainGlareUniform`].
+///
+/// The plugin adds it to every camera with settings. It is read-only: the system that owns
+/// each value overwrites it every frame, which is why they are kept out of the settings.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareDerived {
+    exposure_compensation: f32,
+    gravity_screen_dir: Vec2,
+    rain_amount: f32,
+    camera_right: Vec3,
+    camera_up: Vec3,
+    zoom_factor: f32,
+    wetness_decay: f32,
+    daylight_tint: Vec3,
+    daylight_intensity: f32,
+    outdoor_amount: f32,
+    virtual_resolution: Vec2,
+    beat: f32,
+    viewport: Vec4,
+}
+
+impl Default for RainGlareDerived {
+    fn default() -> Self {
+        Self::DEFAULT
     }
 }
 
-/// Plugin that wires the rain glare effect into the render graph.
-pub struct RainGlarePlugin;
+impl RainGlareDerived {
+    /// Neutral values: an unrolled camera at the default FOV, with every multiplier at 1.
+    pub const DEFAULT: Self = Self {
+        exposure_compensation: 1.0,
+        gravity_screen_dir: Vec2::new(0.0, 1.0),
+        rain_amount: 1.0,
+        camera_right: Vec3::X,
+        camera_up: Vec3::Y,
+        zoom_factor: 1.0,
+        wetness_decay: 1.0,
+        daylight_tint: Vec3::ONE,
+        daylight_intensity: 1.0,
+        outdoor_amount: 1.0,
+        virtual_resolution: Vec2::ZERO,
+        beat: 0.0,
+        viewport: Vec4::new(0.0, 0.0, 1.0, 1.0),
+    };
 
-impl Plugin for RainGlarePlugin {
-    fn build(&self, app: &mut App) {
-        load_internal_asset!(
-            app,
-            RAIN_GLARE_SHADER_HANDLE,
-            "../assets/rain_glare.wgsl",
-            Shader::from_wgsl
-        );
+    /// Multiplier on `intensity` from [`RainGlareExposureCompensation`]; 1.0 while it is off.
+    pub fn exposure_compensation(&self) -> f32 {
+        self.exposure_compensation
+    }
 
-        app.add_plugins((
-            ExtractComponentPlugin::<RainGlareSettings>::default(),
-            UniformComponentPlugin::<RainGlareSettings>::default(),
-        ))
-        // Keep the time parameter in sync with the engine clock.
-        .add_systems(Update, advance_rain_time);
+    /// Screen-space (UV, +y down) direction of world down, from the camera's roll. `wind` is
+    /// applied relative to it, so rain keeps falling "down" when the camera rolls. (0, 1)
+    /// means no roll; see [`RainGlareIgnoreRoll`].
+    pub fn gravity_screen_dir(&self) -> Vec2 {
+        self.gravity_screen_dir
+    }
 
-        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
-            return;
-        };
+    /// Multiplier on both `intensity` and `rain_density` from [`drive_rain_amount`]; 1.0
+    /// while no [`RainGlareDriver`] resource is present.
+    pub fn rain_amount(&self) -> f32 {
+        self.rain_amount
+    }
 
-        render_app
-            .add_render_graph_node::<ViewNodeRunner<RainGlareNode>>(Core3d, RainGlareLabel)
-            .add_render_graph_edges(
-                Core3d,
-                (
-                    Node3d::Tonemapping,
-                    RainGlareLabel,
-                    Node3d::EndMainPassPostProcessing,
-                ),
-            );
+    /// World-space right vector of the camera, for `normal_follow`.
+    pub fn camera_right(&self) -> Vec3 {
+        self.camera_right
     }
 
-    fn finish(&self, app: &mut App) {
-        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
-            return;
-        };
+    /// World-space up vector of the camera, for `normal_follow`.
+    pub fn camera_up(&self) -> Vec3 {
+        self.camera_up
+    }
 
-        render_app.init_resource::<RainGlarePipeline>();
+    /// How far the camera is zoomed in relative to Bevy's default 45° vertical FOV, from its
+    /// [`Projection`] (1 = base FOV or orthographic). Scales `streak_length_px` up and the
+    /// pattern's features with it.
+    pub fn zoom_factor(&self) -> f32 {
+        self.zoom_factor
     }
-}
 
-#[derive(Default)]
-struct RainGlareNode;
+    /// Per-frame wetness decay factor, from `dry_rate` and the frame time while
+    /// [`RainGlareWetness`] is enabled (1 = no decay).
+    pub fn wetness_decay(&self) -> f32 {
+        self.wetness_decay
+    }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
-struct RainGlareLabel;
+    /// Linear RGB color multiplier on the streaks from [`RainGlareTimeOfDay`] (white while
+    /// the resource is absent).
+    pub fn daylight_tint(&self) -> Vec3 {
+        self.daylight_tint
+    }
 
-impl ViewNode for RainGlareNode {
-    type ViewQuery = (
-        &'static ViewTarget,
-        &'static RainGlareSettings,
-        &'static DynamicUniformIndex<RainGlareSettings>,
-    );
+    /// Multiplier on `intensity` from [`RainGlareTimeOfDay`] (1 while the resource is
+    /// absent), e.g. to dim the rain at night.
+    pub fn daylight_intensity(&self) -> f32 {
+        self.daylight_intensity
+    }
 
-    fn run(
-        &self,
-        _graph: &mut RenderGraphContext,
-        render_context: &mut RenderContext,
-        (view_target, _settings, settings_index): QueryItem<Self::ViewQuery>,
-        world: &World,
-    ) -> Result<(), NodeRunError> {
-        let pipeline = world.resource::<RainGlarePipeline>();
-        let view_format = view_target.main_texture_format();
+    /// 0..1 multiplier on `intensity` from [`RainGlareIndoors`] (1 = outdoors, 0 = fully
+    /// indoors); stays 1.0 on cameras without that component.
+    pub fn outdoor_amount(&self) -> f32 {
+        self.outdoor_amount
+    }
 
-        let Some(pipeline_id) = pipeline.pipeline_for_format(view_format) else {
-            return Ok(());
-        };
+    /// Fixed resolution the effect is evaluated at, from [`RainGlareVirtualResolution`];
+    /// (0, 0) = native, the default.
+    pub fn virtual_resolution(&self) -> Vec2 {
+        self.virtual_resolution
+    }
 
-        let pipeline_cache = world.resource::<PipelineCache>();
-        let Some(render_pipeline) = pipeline_cache.get_render_pipeline(*pipeline_id) else {
-            return Ok(());
-        };
+    /// Current 0..1 beat envelope from [`RainGlareBeat`]; stays 0 while that resource is
+    /// absent.
+    pub fn beat(&self) -> f32 {
+        self.beat
+    }
 
-        let settings_uniforms = world.resource::<ComponentUniforms<RainGlareSettings>>();
-        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
-            return Ok(());
-        };
+    /// The camera's [`Camera::viewport`] as a fraction of its render target: origin in `xy`,
+    /// size in `zw`; (0, 0, 1, 1) without a viewport. The pattern is laid out over it, so
+    /// each eye of a stereo pair sharing one target gets the same pattern.
+    pub fn viewport(&self) -> Vec4 {
+        self.viewport
+    }
+}
 
-        let post_process = view_target.post_process_write();
+/// A rain camera's uniform: its [`RainGlareSettings`] together with its [`RainGlareDerived`]
+/// values, laid out as the WGSL `RainGlareSettings` struct of `rain_glare_types.wgsl`.
+///
+/// The plugin extracts one per camera into the render world, where the effect's systems and
+/// node read it. It implements `ShaderType`, so a material can bind a camera's values with a
+/// plain `#[uniform(n)]` field in its `AsBindGroup`; build it with [`Self::new`].
+#[derive(Component, Clone, Copy, Debug, ShaderType)]
+pub struct RainGlareUniform {
+    intensity: f32,
+    threshold: f32,
+    streak_length_px: f32,
+    rain_density: f32,
+    wind: Vec2,
+    speed: f32,
+    time: f32,
+    pattern_scale: f32,
+    mask_thickness_px: f32,
+    snap_to_pixel: f32,
+    tail_quant_steps: f32,
+    view_angle_factor: f32,
+    edge_softness_px: f32,
+    threshold_color_weights: Vec3,
+    brightness_gamma: f32,
+    dry_patch_scale: f32,
+    dry_patch_amount: f32,
+    exposure_compensation: f32,
+    core_rolloff: f32,
+    sparkle_amount: f32,
+    length_from_brightness: f32,
+    gravity_screen_dir: Vec2,
+    sky_only: f32,
+    rain_amount: f32,
+    head_fade: f32,
+    tail_fade: f32,
+    uv_offset: Vec2,
+    highlight_bleed_px: f32,
+    motion_response: f32,
+    outdoor_amount: f32,
+    virtual_resolution: Vec2,
+    beat_response: f32,
+    beat: f32,
+    normal_follow: f32,
+    camera_right: Vec3,
+    camera_up: Vec3,
+    seed: f32,
+    zoom_factor: f32,
+    cross_blur_px: f32,
+    vertical_bias: f32,
+    dry_rate: f32,
+    wetness_gain: f32,
+    wetness_decay: f32,
+    streak_spacing: f32,
+    streak_count_scale: f32,
+    daylight_tint: Vec3,
+    daylight_intensity: f32,
+    contrast_preserve: f32,
+    head_color: Vec3,
+    tail_color: Vec3,
+    viewport: Vec4,
+}
 
-        let bind_group = render_context.render_device().create_bind_group(
-            "rain_glare_bind_group",
-            &pipeline.layout,
-            &BindGroupEntries::sequential((
-                post_process.source,
-                &pipeline.sampler,
-                settings_binding.clone(),
-            )),
-        );
+impl RainGlareUniform {
+    /// Packs a camera's settings and derived values in the shader's layout.
+    pub fn new(settings: &RainGlareSettings, derived: &RainGlareDerived) -> Self {
+        Self {
+            intensity: settings.intensity,
+            threshold: settings.threshold,
+            streak_length_px: settings.streak_length_px,
+            rain_density: settings.rain_density,
+            wind: settings.wind,
+            speed: settings.speed,
+            time: settings.time,
+            pattern_scale: settings.pattern_scale,
+            mask_thickness_px: settings.mask_thickness_px,
+            snap_to_pixel: settings.snap_to_pixel,
+            tail_quant_steps: settings.tail_quant_steps,
+            view_angle_factor: settings.view_angle_factor,
+            edge_softness_px: settings.edge_softness_px,
+            threshold_color_weights: settings.threshold_color_weights,
+            brightness_gamma: settings.brightness_gamma,
+            dry_patch_scale: settings.dry_patch_scale,
+            dry_patch_amount: settings.dry_patch_amount,
+            exposure_compensation: derived.exposure_compensation,
+            core_rolloff: settings.core_rolloff,
+            sparkle_amount: settings.sparkle_amount,
+            length_from_brightness: settings.length_from_brightness,
+            gravity_screen_dir: derived.gravity_screen_dir,
+            sky_only: settings.sky_only,
+            rain_amount: derived.rain_amount,
+            head_fade: settings.head_fade,
+            tail_fade: settings.tail_fade,
+            uv_offset: settings.uv_offset,
+            highlight_bleed_px: settings.highlight_bleed_px,
+            motion_response: settings.motion_response,
+            outdoor_amount: derived.outdoor_amount,
+            virtual_resolution: derived.virtual_resolution,
+            beat_response: settings.beat_response,
+            beat: derived.beat,
+            normal_follow: settings.normal_follow,
+            camera_right: derived.camera_right,
+            camera_up: derived.camera_up,
+            seed: settings.seed,
+            zoom_factor: derived.zoom_factor,
+            cross_blur_px: settings.cross_blur_px,
+            vertical_bias: settings.vertical_bias,
+            dry_rate: settings.dry_rate,
+            wetness_gain: settings.wetness_gain,
+            wetness_decay: derived.wetness_decay,
+            streak_spacing: settings.streak_spacing,
+            streak_count_scale: settings.streak_count_scale,
+            daylight_tint: derived.daylight_tint,
+            daylight_intensity: derived.daylight_intensity,
+            contrast_preserve: settings.contrast_preserve,
+            head_color: linear_rgb(settings.head_color),
+            tail_color: linear_rgb(settings.tail_color),
+            viewport: derived.viewport,
+        }
+    }
 
-        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-            label: Some("rain_glare_pass"),
-            color_attachments: &[Some(RenderPassColorAttachment {
-                view: post_process.destination,
-                resolve_target: None,
-                ops: Operations::default(),
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-        });
+    /// The fixed size of the streak mask for [`RainGlareVirtualResolution`], if set.
+    fn virtual_size(&self) -> Option<UVec2> {
+        self.virtual_resolution
+            .cmpgt(Vec2::ZERO)
+            .all()
+            .then(|| self.virtual_resolution.as_uvec2())
+    }
 
-        render_pass.set_render_pipeline(render_pipeline);
-        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
-        render_pass.draw(0..3, 0..1);
+    /// Whether the effect draws anything: the effective intensity and rain density, after
+    /// every multiplier the plugin applies, are both above zero.
+    pub fn is_active(&self) -> bool {
+        let intensity = self.intensity
+            * self.exposure_compensation
+            * self.rain_amount
+            * self.outdoor_amount
+            * self.daylight_intensity;
+        intensity > 0.0 && self.rain_density * self.rain_amount > 0.0
+    }
+}
 
-        Ok(())
+impl ExtractComponent for RainGlareSettings {
+    type QueryData = (&'static Self, Option<&'static RainGlareDerived>);
+    type QueryFilter = ();
+    type Out = RainGlareUniform;
+
+    fn extract_component((settings, derived): QueryItem<'_, Self::QueryData>) -> Option<Self::Out> {
+        // A camera spawned this frame gets its derived values on the next update.
+        let derived = derived.unwrap_or(&RainGlareDerived::DEFAULT);
+        Some(RainGlareUniform::new(settings, derived))
     }
 }
 
-#[derive(Resource)]
-struct RainGlarePipeline {
-    layout: BindGroupLayout,
-    sampler: Sampler,
-    pipelines: HashMap<TextureFormat, CachedRenderPipelineId>,
+// Uniform layout baseline, checked at compile time. `ShaderType` lays `RainGlareUniform` out
+// with WGSL's rules, so it matches `RainGlareSettings` in `rain_glare_types.wgsl` as long as
+// both list the same fields in the same order with the same types. Byte offsets:
+//
+//   0 intensity           4 threshold            8 streak_length_px    12 rain_density
+//  16 wind (vec2)        24 speed               28 time               32 pattern_scale
+//  36 mask_thickness_px  40 snap_to_pixel       44 tail_quant_steps   48 view_angle_factor
+//  52 edge_softness_px   64 threshold_color_weights (vec3, 16-aligned: 56..64 is padding)
+//  76 brightness_gamma   80 dry_patch_scale     84 dry_patch_amount   88 exposure_compensation
+//  92 core_rolloff       96 sparkle_amount     100 length_from_brightness
+// 104 gravity_screen_dir (vec2)                112 sky_only          116 rain_amount
+// 120 head_fade         124 tail_fade          128 uv_offset (vec2)  136 highlight_bleed_px
+// 140 motion_response   144 outdoor_amount     152 virtual_resolution (vec2, 8-aligned)
+// 160 beat_response     164 beat               168 normal_follow
+// 176 camera_right (vec3, 16-aligned)          192 camera_up (vec3)  204 seed
+// 208 zoom_factor       212 cross_blur_px      216 vertical_bias      220 dry_rate
+// 224 wetness_gain      228 wetness_decay      232 streak_spacing     236 streak_count_scale
+// 240 daylight_tint (vec3, 16-aligned)         252 daylight_intensity
+// 256 contrast_preserve  272 head_color (vec3, 16-aligned)  288 tail_color (vec3)
+// 304 viewport (vec4, 16-aligned)
+// (320 bytes)
+//
+// To add a field: append it to both structs (appending never moves existing offsets; note
+// that `Vec2` is 8-aligned and `Vec3` 16-aligned) and fill it in `RainGlareUniform::new`.
+// A user-facing setting also goes into `RainGlareSettings` with its `DEFAULT`, the patch
+// type and the flat array. Then update the table and `SETTINGS_UNIFORM_SIZE`.
+const SETTINGS_UNIFORM_SIZE: u64 = 320;
+const _: () = assert!(
+    RainGlareUniform::SHADER_SIZE.get() == SETTINGS_UNIFORM_SIZE,
+    "RainGlareUniform layout changed; update the baseline and rain_glare_types.wgsl"
+);
+
+/// Sparse update for [`RainGlareSettings`]: only `Some` fields are applied.
+///
+/// Pairs with [`RainGlareSettings::to_array`] for sync layers that send only the fields
+/// that changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RainGlareSettingsPatch {
+    pub intensity: Option<f32>,
+    pub threshold: Option<f32>,
+    pub streak_length_px: Option<f32>,
+    pub rain_density: Option<f32>,
+    pub wind: Option<Vec2>,
+    pub speed: Option<f32>,
+    pub time: Option<f32>,
+    pub pattern_scale: Option<f32>,
+    pub mask_thickness_px: Option<f32>,
+    pub snap_to_pixel: Option<f32>,
+    pub tail_quant_steps: Option<f32>,
+    pub view_angle_factor: Option<f32>,
+    pub edge_softness_px: Option<f32>,
+    pub threshold_color_weights: Option<Vec3>,
+    pub brightness_gamma: Option<f32>,
+    pub dry_patch_scale: Option<f32>,
+    pub dry_patch_amount: Option<f32>,
+    pub core_rolloff: Option<f32>,
+    pub sparkle_amount: Option<f32>,
+    pub length_from_brightness: Option<f32>,
+    pub sky_only: Option<f32>,
+    pub head_fade: Option<f32>,
+    pub tail_fade: Option<f32>,
+    pub uv_offset: Option<Vec2>,
+    pub highlight_bleed_px: Option<f32>,
+    pub motion_response: Option<f32>,
+    pub beat_response: Option<f32>,
+    pub normal_follow: Option<f32>,
+    pub seed: Option<f32>,
+    pub cross_blur_px: Option<f32>,
+    pub vertical_bias: Option<f32>,
+    pub dry_rate: Option<f32>,
+    pub wetness_gain: Option<f32>,
+    pub streak_spacing: Option<f32>,
+    pub streak_count_scale: Option<f32>,
+    pub contrast_preserve: Option<f32>,
+    pub head_color: Option<Srgba>,
+    pub tail_color: Option<Srgba>,
 }
 
-impl RainGlarePipeline {
-    fn pipeline_for_format(&self, format: TextureFormat) -> Option<&CachedRenderPipelineId> {
-        self.pipelines.get(&format)
+/// A 3D camera set up the way the effect is tuned to look: HDR, TonyMcMapface tonemapping
+/// and natural bloom, plus default [`RainGlareSettings`].
+///
+/// Purely a convenience; adding `RainGlareSettings` to any `Camera3dBundle` still works.
+#[derive(Bundle)]
+pub struct RainGlareCameraBundle {
+    pub camera: Camera3dBundle,
+    pub bloom: BloomSettings,
+    pub settings: RainGlareSettings,
+}
+
+impl Default for RainGlareCameraBundle {
+    fn default() -> Self {
+        Self {
+            camera: Camera3dBundle {
+                camera: Camera {
+                    hdr: true,
+                    ..default()
+                },
+                tonemapping: Tonemapping::TonyMcMapface,
+                ..default()
+            },
+            bloom: BloomSettings::NATURAL,
+            settings: RainGlareSettings::default(),
+        }
     }
 }
 
-impl FromWorld for RainGlarePipeline {
-    fn from_world(world: &mut World) -> Self {
-        let render_device = world.resource::<RenderDevice>();
+impl RainGlareCameraBundle {
+    /// Shorthand for positioning the camera while keeping the other defaults.
+    pub fn from_transform(transform: Transform) -> Self {
+        let mut bundle = Self::default();
+        bundle.camera.transform = transform;
+        bundle
+    }
+}
 
-        let layout = render_device.create_bind_group_layout(
-            "rain_glare_bind_group_layout",
-            &BindGroupLayoutEntries::sequential(
-                ShaderStages::FRAGMENT,
-                (
-                    texture_2d(TextureSampleType::Float { filterable: true }),
-                    sampler(SamplerBindingType::Filtering),
-                    uniform_buffer::<RainGlareSettings>(true),
-                ),
+/// [`EntityCommands`] sugar for taking the effect off a camera.
+pub trait RainGlareEntityCommandsExt {
+    /// Removes [`RainGlareSettings`] and every companion component this crate defines
+    /// (fades, ramps, transitions, LOD, masks, ...). Components that aren't present are
+    /// skipped; the camera itself is left untouched.
+    fn remove_rain_glare(&mut self) -> &mut Self;
+}
+
+impl RainGlareEntityCommandsExt for EntityCommands<'_> {
+    fn remove_rain_glare(&mut self) -> &mut Self {
+        self.remove::<(
+            RainGlareSettings,
+            RainGlareFadeOut,
+            RainGlareIndoors,
+            RainGlareWindRelax,
+            RainGlareVirtualResolution,
+            RainGlareIgnoreRoll,
+            RainGlareTimeSource,
+            RainGlareHistory,
+            RainGlareFreeze,
+            RainGlareRamp,
+            RainGlareTransition,
+            RainGlareLod,
+            RainGlareExclusionMask,
+            RainGlareCaptureOutput,
+            (
+                RainGlareLetterboxAware,
+                RainGlareFormatPolicy,
+                RainGlareMotionBlurAware,
+                RainGlareDerived,
             ),
-        );
+        )>()
+    }
+}
 
-        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
-        let shader = RAIN_GLARE_SHADER_HANDLE.clone();
+impl RainGlareSettings {
+    /// The [`Default`] settings, usable in const contexts such as presets.
+    pub const DEFAULT: Self = Self {
+        // Look and animation.
+        intensity: 0.35,
+        threshold: 0.65,
+        rain_density: 0.55,
+        wind: Vec2::new(0.10, 1.0),
+        speed: 1.2,
+        time: 0.0,
+        seed: 0.0,
 
-        let mut pipelines = HashMap::new();
-        let pipeline_cache = world.resource_mut::<PipelineCache>();
-        for format in [
-            TextureFormat::bevy_default(),
-            ViewTarget::TEXTURE_FORMAT_HDR,
+        // Pattern and spacing.
+        pattern_scale: 3.0,
+        mask_thickness_px: 0.75,
+        snap_to_pixel: 1.0,
+        tail_quant_steps: 8.0,
+        streak_spacing: 1.0,
+        streak_count_scale: 1.0,
+        uv_offset: Vec2::ZERO,
+
+        // Streak shape.
+        streak_length_px: 96.0,
+        edge_softness_px: 0.0,
+        core_rolloff: 0.0,
+        sparkle_amount: 0.0,
+        length_from_brightness: 0.0,
+        head_fade: 0.0,
+        tail_fade: 1.0,
+        highlight_bleed_px: 0.0,
+        cross_blur_px: 0.0,
+        vertical_bias: 0.0,
+
+        // Brightness and colour.
+        threshold_color_weights: Vec3::new(0.2126, 0.7152, 0.0722),
+        brightness_gamma: 1.0,
+        contrast_preserve: 0.0,
+        head_color: Srgba::WHITE,
+        tail_color: Srgba::WHITE,
+
+        // Camera and scene response.
+        view_angle_factor: 1.0,
+        sky_only: 0.0,
+        motion_response: 0.0,
+        beat_response: 0.0,
+        normal_follow: 0.0,
+
+        // Surface coverage and wetness.
+        dry_patch_scale: 256.0,
+        dry_patch_amount: 0.0,
+        dry_rate: 0.5,
+        wetness_gain: 1.0,
+    };
+
+    /// Sparse, fine streaks for a light shower.
+    pub const LIGHT_DRIZZLE: Self = Self {
+        intensity: 0.2,
+        rain_density: 0.25,
+        streak_length_px: 64.0,
+        speed: 0.8,
+        mask_thickness_px: 0.6,
+        ..Self::DEFAULT
+    };
+
+    /// Dense, long, bright streaks for heavy rain.
+    pub const DOWNPOUR: Self = Self {
+        intensity: 0.6,
+        rain_density: 0.95,
+        streak_length_px: 160.0,
+        speed: 2.2,
+        wind: Vec2::new(0.25, 1.0),
+        mask_thickness_px: 0.9,
+        ..Self::DEFAULT
+    };
+
+    /// Builds settings from the core parameters, keeping the defaults for everything else,
+    /// and rejects non-finite or out-of-range values instead of letting them through. Meant
+    /// for config loaded from untrusted sources.
+    pub fn try_new(
+        intensity: f32,
+        threshold: f32,
+        streak_length_px: f32,
+        rain_density: f32,
+        wind: Vec2,
+        speed: f32,
+    ) -> Result<Self, RainGlareSettingsError> {
+        let settings = Self {
+            intensity,
+            threshold,
+            streak_length_px,
+            rain_density,
+            wind,
+            speed,
+            ..Self::DEFAULT
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Checks the core parameters, `pattern_scale` and `mask_thickness_px` against the
+    /// ranges the plugin clamps them to: `intensity` up to [`Self::MAX_INTENSITY`],
+    /// `rain_density` up to [`Self::MAX_DENSITY`], `pattern_scale` from
+    /// [`Self::MIN_PATTERN_SCALE`] and `mask_thickness_px` up to [`Self::MAX_THICKNESS_RATIO`]
+    /// times `streak_length_px`.
+    pub fn validate(&self) -> Result<(), RainGlareSettingsError> {
+        let unbounded = f32::INFINITY;
+        for (field, value, min, max) in [
+            ("intensity", self.intensity, 0.0, Self::MAX_INTENSITY),
+            ("threshold", self.threshold, 0.0, unbounded),
+            ("streak_length_px", self.streak_length_px, 0.0, unbounded),
+            ("rain_density", self.rain_density, 0.0, Self::MAX_DENSITY),
+            ("wind.x", self.wind.x, -unbounded, unbounded),
+            ("wind.y", self.wind.y, -unbounded, unbounded),
+            ("speed", self.speed, 0.0, unbounded),
+            (
+                "pattern_scale",
+                self.pattern_scale,
+                Self::MIN_PATTERN_SCALE,
+                unbounded,
+            ),
+            (
+                "mask_thickness_px",
+                self.mask_thickness_px,
+                0.0,
+                Self::MAX_THICKNESS_RATIO * self.streak_length_px,
+            ),
         ] {
-            let id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
-                label: Some("rain_glare_pipeline".into()),
-                layout: vec![layout.clone()],
-                vertex: fullscreen_shader_vertex_state(),
-                fragment: Some(FragmentState {
-                    shader: shader.clone(),
-                    shader_defs: vec![],
-                    entry_point: "fragment".into(),
-                    targets: vec![Some(ColorTargetState {
-                        format,
-                        blend: None,
-                        write_mask: ColorWrites::ALL,
-                    })],
-                }),
-                primitive: PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: MultisampleState::default(),
-                push_constant_ranges: vec![],
-            });
-            pipelines.insert(format, id);
+            if !value.is_finite() {
+                return Err(RainGlareSettingsError::NonFinite { field, value });
+            }
+            if !(min..=max).contains(&value) {
+                return Err(RainGlareSettingsError::OutOfRange {
+                    field,
+                    value,
+                    min,
+                    max,
+                });
+            }
         }
+        Ok(())
+    }
 
-        Self {
-            layout,
-            sampler,
-            pipelines,
+    /// Whether these settings draw anything on their own: `intensity` and `rain_density`
+    /// are both above zero. [`RainGlareUniform::is_active`] also applies the multipliers
+    /// the plugin derives for the camera.
+    pub fn is_active(&self) -> bool {
+        self.intensity > 0.0 && self.rain_density > 0.0
+    }
+
+    /// Default ceiling on the `intensity` uploaded to the GPU, see [`RainGlareLimits`].
+    pub const MAX_INTENSITY: f32 = 4.0;
+
+    /// Largest `rain_density` [`Self::validate`] accepts, and the cap of
+    /// [`RainGlareQuality::HIGH`].
+    pub const MAX_DENSITY: f32 = 10.0;
+
+    /// Largest `mask_thickness_px / streak_length_px` that still reads as a streak. The
+    /// thickness is measured from the line's center, so beyond this the streak is wider
+    /// than it is long.
+    pub const MAX_THICKNESS_RATIO: f32 = 0.5;
+
+    /// Whether `mask_thickness_px` is so large against `streak_length_px` that the streaks
+    /// degenerate into blobs. Debug builds warn (once) when a camera's settings do; see
+    /// [`RainGlareLimits::clamp_thickness`] to clamp them instead.
+    pub const fn has_blob_streaks(&self) -> bool {
+        self.mask_thickness_px > Self::MAX_THICKNESS_RATIO * self.streak_length_px
+    }
+
+    const fn clamped_mask_thickness(mask_thickness_px: f32, streak_length_px: f32) -> f32 {
+        mask_thickness_px.min(Self::MAX_THICKNESS_RATIO * streak_length_px.max(0.0))
+    }
+
+    /// Smallest `pattern_scale` uploaded to the GPU; zero, negative or NaN scales are
+    /// raised to it, as they would divide by zero or invert the pattern.
+    pub const MIN_PATTERN_SCALE: f32 = 1e-3;
+
+    const fn sanitized_pattern_scale(pattern_scale: f32) -> f32 {
+        if pattern_scale >= Self::MIN_PATTERN_SCALE {
+            pattern_scale
+        } else {
+            Self::MIN_PATTERN_SCALE
         }
     }
-}
 
-/* fn advance_rain_time(time: Res<Time>, mut query: Query<&mut RainGlareSettings>) {
-    for mut settings in &mut query {
-        settings.time += time.delta_seconds();
+    /// Length of the flat representation produced by [`Self::to_array`].
+    pub const ARRAY_LEN: usize = 48;
+
+    /// Flattens every field into a fixed-size float packet, e.g. for network sync.
+    ///
+    /// Fields appear in declaration order, with vectors expanded component-wise:
+    /// `intensity, threshold, streak_length_px, rain_density, wind.{x,y}, speed, time,
+    /// pattern_scale, mask_thickness_px, snap_to_pixel, tail_quant_steps,
+    /// view_angle_factor, edge_softness_px, threshold_color_weights.{x,y,z},
+    /// brightness_gamma, dry_patch_scale, dry_patch_amount, core_rolloff, sparkle_amount,
+    /// length_from_brightness, sky_only, head_fade, tail_fade, uv_offset.{x,y},
+    /// highlight_bleed_px, motion_response, beat_response, normal_follow, seed,
+    /// cross_blur_px, vertical_bias, dry_rate, wetness_gain, streak_spacing,
+    /// streak_count_scale, contrast_preserve, head_color.{red,green,blue,alpha},
+    /// tail_color.{red,green,blue,alpha}`.
+    pub fn to_array(&self) -> [f32; Self::ARRAY_LEN] {
+        [
+            self.intensity,
+            self.threshold,
+            self.streak_length_px,
+            self.rain_density,
+            self.wind.x,
+            self.wind.y,
+            self.speed,
+            self.time,
+            self.pattern_scale,
+            self.mask_thickness_px,
+            self.snap_to_pixel,
+            self.tail_quant_steps,
+            self.view_angle_factor,
+            self.edge_softness_px,
+            self.threshold_color_weights.x,
+            self.threshold_color_weights.y,
+            self.threshold_color_weights.z,
+            self.brightness_gamma,
+            self.dry_patch_scale,
+            self.dry_patch_amount,
+            self.core_rolloff,
+            self.sparkle_amount,
+            self.length_from_brightness,
+            self.sky_only,
+            self.head_fade,
+            self.tail_fade,
+            self.uv_offset.x,
+            self.uv_offset.y,
+            self.highlight_bleed_px,
+            self.motion_response,
+            self.beat_response,
+            self.normal_follow,
+            self.seed,
+            self.cross_blur_px,
+            self.vertical_bias,
+            self.dry_rate,
+            self.wetness_gain,
+            self.streak_spacing,
+            self.streak_count_scale,
+            self.contrast_preserve,
+            self.head_color.red,
+            self.head_color.green,
+            self.head_color.blue,
+            self.head_color.alpha,
+            self.tail_color.red,
+            self.tail_color.green,
+            self.tail_color.blue,
+            self.tail_color.alpha,
+        ]
     }
-} */
-fn advance_rain_time(
-    time: Res<Time>,
-    mut q: Query<(&GlobalTransform, &mut RainGlareSettings), With<Camera3d>>,
-) {
-    let t = time.elapsed_seconds();
 
-    for (global_transform, mut settings) in &mut q {
-        settings.time = t;
+    /// Linear interpolation of every field towards `other` (`t` = 0 gives `self`, 1 gives
+    /// `other`). `t` is not clamped.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let a = self.to_array();
+        let b = other.to_array();
+        Self::from_array(std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t))
+    }
 
-        // World-space view direction (forward).
-        // GlobalTransform::forward() returns Dir3; convert to Vec3.
-        let forward: Vec3 = global_transform.forward().into();
+    /// Inverse of [`Self::to_array`]; see it for the field order.
+    pub fn from_array(a: [f32; Self::ARRAY_LEN]) -> Self {
+        Self {
+            intensity: a[0],
+            threshold: a[1],
+            streak_length_px: a[2],
+            rain_density: a[3],
+            wind: Vec2::new(a[4], a[5]),
+            speed: a[6],
+            time: a[7],
+            pattern_scale: a[8],
+            mask_thickness_px: a[9],
+            snap_to_pixel: a[10],
+            tail_quant_steps: a[11],
+            view_angle_factor: a[12],
+            edge_softness_px: a[13],
+            threshold_color_weights: Vec3::new(a[14], a[15], a[16]),
+            brightness_gamma: a[17],
+            dry_patch_scale: a[18],
+            dry_patch_amount: a[19],
+            core_rolloff: a[20],
+            sparkle_amount: a[21],
+            length_from_brightness: a[22],
+            sky_only: a[23],
+            head_fade: a[24],
+            tail_fade: a[25],
+            uv_offset: Vec2::new(a[26], a[27]),
+            highlight_bleed_px: a[28],
+            motion_response: a[29],
+            beat_response: a[30],
+            normal_follow: a[31],
+            seed: a[32],
+            cross_blur_px: a[33],
+            vertical_bias: a[34],
+            dry_rate: a[35],
+            wetness_gain: a[36],
+            streak_spacing: a[37],
+            streak_count_scale: a[38],
+            contrast_preserve: a[39],
+            head_color: Srgba::new(a[40], a[41], a[42], a[43]),
+            tail_color: Srgba::new(a[44], a[45], a[46], a[47]),
+        }
+    }
 
-        // World up (assuming Y-up). Change if you use a different up-axis.
-        let world_up = Vec3::Y;
+    /// Sets `head_color` and `tail_color` from any Bevy colors.
+    pub fn with_streak_gradient(mut self, head: impl Into<Color>, tail: impl Into<Color>) -> Self {
+        self.head_color = Srgba::from(head.into());
+        self.tail_color = Srgba::from(tail.into());
+        self
+    }
 
-        // How much the camera is pointing up/down.
-        let vertical = forward.dot(world_up);           // -1..1
-        let horizon = (1.0 - vertical.abs()).clamp(0.0, 1.0);
+    /// Rust source for a `RainGlareSettings { .. }` initializer with the current values,
+    /// e.g. to paste settings found with live tweaking into a preset constant.
+    ///
+    /// Lists every authored field and ends in `..RainGlareSettings::DEFAULT` for the ones
+    /// written by the plugin's own systems (see [`RainGlareTransition`]). Floats are printed
+    /// so that they parse back to exactly the same value.
+    pub fn to_rust_literal(&self) -> String {
+        fn float(v: f32) -> String {
+            if v.is_nan() {
+                "f32::NAN".into()
+            } else if v.is_infinite() {
+                if v > 0.0 {
+                    "f32::INFINITY"
+                } else {
+                    "f32::NEG_INFINITY"
+                }
+                .into()
+            } else {
+                format!("{v:?}")
+            }
+        }
+        let vec2 = |v: Vec2| format!("Vec2::new({}, {})", float(v.x), float(v.y));
+        let vec3 = |v: Vec3| format!("Vec3::new({}, {}, {})", float(v.x), float(v.y), float(v.z));
+        let srgba = |c: Srgba| {
+            format!(
+                "Srgba::new({}, {}, {}, {})",
+                float(c.red),
+                float(c.green),
+                float(c.blue),
+                float(c.alpha)
+            )
+        };
 
-        // Sharpen so it’s strong near the horizon, fades faster near zenith/nadir.
-        let exponent = 2.0;
-        let angle_factor = horizon.powf(exponent);
+        let mut out = String::from("RainGlareSettings {\n");
+        for (name, value) in [
+            ("intensity", float(self.intensity)),
+            ("threshold", float(self.threshold)),
+            ("streak_length_px", float(self.streak_length_px)),
+            ("rain_density", float(self.rain_density)),
+            ("wind", vec2(self.wind)),
+            ("speed", float(self.speed)),
+            ("pattern_scale", float(self.pattern_scale)),
+            ("mask_thickness_px", float(self.mask_thickness_px)),
+            ("snap_to_pixel", float(self.snap_to_pixel)),
+            ("tail_quant_steps", float(self.tail_quant_steps)),
+            ("edge_softness_px", float(self.edge_softness_px)),
+            (
+                "threshold_color_weights",
+                vec3(self.threshold_color_weights),
+            ),
+            ("brightness_gamma", float(self.brightness_gamma)),
+            ("dry_patch_scale", float(self.dry_patch_scale)),
+            ("dry_patch_amount", float(self.dry_patch_amount)),
+            ("core_rolloff", float(self.core_rolloff)),
+            ("sparkle_amount", float(self.sparkle_amount)),
+            ("length_from_brightness", float(self.length_from_brightness)),
+            ("sky_only", float(self.sky_only)),
+            ("head_fade", float(self.head_fade)),
+            ("tail_fade", float(self.tail_fade)),
+            ("uv_offset", vec2(self.uv_offset)),
+            ("highlight_bleed_px", float(self.highlight_bleed_px)),
+            ("motion_response", float(self.motion_response)),
+            ("beat_response", float(self.beat_response)),
+            ("normal_follow", float(self.normal_follow)),
+            ("seed", float(self.seed)),
+            ("cross_blur_px", float(self.cross_blur_px)),
+            ("vertical_bias", float(self.vertical_bias)),
+            ("dry_rate", float(self.dry_rate)),
+            ("wetness_gain", float(self.wetness_gain)),
+            ("streak_spacing", float(self.streak_spacing)),
+            ("streak_count_scale", float(self.streak_count_scale)),
+            ("contrast_preserve", float(self.contrast_preserve)),
+            ("head_color", srgba(self.head_color)),
+            ("tail_color", srgba(self.tail_color)),
+        ] {
+            out.push_str(&format!("    {name}: {value},\n"));
+        }
+        out.push_str("    ..RainGlareSettings::DEFAULT\n}");
+        out
+    }
 
-        settings.view_angle_factor = angle_factor;
+    /// Copies the fields written by the plugin's own systems (`time`, `view_angle_factor`)
+    /// from `live`, so swapping in authored settings doesn't reset them.
+    fn with_live_fields_of(self, live: &Self) -> Self {
+        Self {
+            time: live.time,
+            view_angle_factor: live.view_angle_factor,
+            ..self
+        }
     }
-}
\ No newline at end of file
+
+    /// Overwrites every field that is `Some` in `patch`, leaving the rest untouched.
+    pub fn apply_patch(&mut self, patch: RainGlareSettingsPatch) {
+        if let Some(v) = patch.intensity {
+            self.intensity = v;
+        }
+        if let Some(v) = patch.threshold {
+            self.threshold = v;
+        }
+        if let Some(v) = patch.streak_length_px {
+            self.streak_length_px = v;
+        }
+        if let Some(v) = patch.rain_density {
+            self.rain_density = v;
+        }
+        if let Some(v) = patch.wind {
+            self.wind = v;
+        }
+        if let Some(v) = patch.speed {
+            self.speed = v;
+        }
+        if let Some(v) = patch.time {
+            self.time = v;
+        }
+        if let Some(v) = patch.pattern_scale {
+            self.pattern_scale = v;
+        }
+        if let Some(v) = patch.mask_thickness_px {
+            self.mask_thickness_px = v;
+        }
+        if let Some(v) = patch.snap_to_pixel {
+            self.snap_to_pixel = v;
+        }
+        if let Some(v) = patch.tail_quant_steps {
+            self.tail_quant_steps = v;
+        }
+        if let Some(v) = patch.view_angle_factor {
+            self.view_angle_factor = v;
+        }
+        if let Some(v) = patch.edge_softness_px {
+            self.edge_softness_px = v;
+        }
+        if let Some(v) = patch.threshold_color_weights {
+            self.threshold_color_weights = v;
+        }
+        if let Some(v) = patch.brightness_gamma {
+            self.brightness_gamma = v;
+        }
+        if let Some(v) = patch.dry_patch_scale {
+            self.dry_patch_scale = v;
+        }
+        if let Some(v) = patch.dry_patch_amount {
+            self.dry_patch_amount = v;
+        }
+        if let Some(v) = patch.core_rolloff {
+            self.core_rolloff = v;
+        }
+        if let Some(v) = patch.sparkle_amount {
+            self.sparkle_amount = v;
+        }
+        if let Some(v) = patch.length_from_brightness {
+            self.length_from_brightness = v;
+        }
+        if let Some(v) = patch.sky_only {
+            self.sky_only = v;
+        }
+        if let Some(v) = patch.head_fade {
+            self.head_fade = v;
+        }
+        if let Some(v) = patch.tail_fade {
+            self.tail_fade = v;
+        }
+        if let Some(v) = patch.uv_offset {
+            self.uv_offset = v;
+        }
+        if let Some(v) = patch.highlight_bleed_px {
+            self.highlight_bleed_px = v;
+        }
+        if let Some(v) = patch.motion_response {
+            self.motion_response = v;
+        }
+        if let Some(v) = patch.beat_response {
+            self.beat_response = v;
+        }
+        if let Some(v) = patch.normal_follow {
+            self.normal_follow = v;
+        }
+        if let Some(v) = patch.seed {
+            self.seed = v;
+        }
+        if let Some(v) = patch.cross_blur_px {
+            self.cross_blur_px = v;
+        }
+        if let Some(v) = patch.vertical_bias {
+            self.vertical_bias = v;
+        }
+        if let Some(v) = patch.dry_rate {
+            self.dry_rate = v;
+        }
+        if let Some(v) = patch.wetness_gain {
+            self.wetness_gain = v;
+        }
+        if let Some(v) = patch.streak_spacing {
+            self.streak_spacing = v;
+        }
+        if let Some(v) = patch.streak_count_scale {
+            self.streak_count_scale = v;
+        }
+        if let Some(v) = patch.contrast_preserve {
+            self.contrast_preserve = v;
+        }
+        if let Some(v) = patch.head_color {
+            self.head_color = v;
+        }
+        if let Some(v) = patch.tail_color {
+            self.tail_color = v;
+        }
+    }
+}
+
+/// Lets streaks tail off when `rain_density` is set to 0 instead of vanishing instantly.
+///
+/// When the density drops to zero, the last non-zero density is held (so the current
+/// streaks stay in place) while `intensity` decays to 0 over `duration` seconds. Afterwards
+/// the density is set to 0 and the original intensity restored, ready for the next shower.
+/// Setting a new non-zero density mid-fade cancels it. Intensity writes made during the fade
+/// are overwritten.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct RainGlareFadeOut {
+    /// Fade length in seconds.
+    pub duration: f32,
+    #[reflect(ignore)]
+    last_density: f32,
+    #[reflect(ignore)]
+    last_intensity: f32,
+    /// Seconds into the current fade, if one is running.
+    #[reflect(ignore)]
+    elapsed: Option<f32>,
+}
+
+impl RainGlareFadeOut {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            last_density: 0.0,
+            last_intensity: 0.0,
+            elapsed: None,
+        }
+    }
+}
+
+/// Gameplay flag that suppresses the effect while the camera is indoors, e.g. set from
+/// trigger volumes. Cameras without it are treated as outdoors.
+///
+/// Drives [`RainGlareDerived::outdoor_amount`], crossfading over `crossfade` seconds
+/// (0 = switch instantly) so crossing a doorway doesn't pop. Fully indoors, the pass is
+/// skipped altogether.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareIndoors {
+    pub indoors: bool,
+    pub crossfade: f32,
+}
+
+/// Eases [`RainGlareSettings::wind`] back to a calm `base` once gusts stop, instead of
+/// leaving it at the last gust's value. Cameras without it keep whatever wind they are given.
+///
+/// Each frame the wind relaxes exponentially towards `base` at `rate` per second (higher is
+/// faster). A frame in which something else changed the wind counts as gust input: the
+/// wind is left alone that frame and relaxes from the new value after.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareWindRelax {
+    pub base: Vec2,
+    pub rate: f32,
+}
+
+/// Evaluates the effect at a fixed virtual resolution (e.g. 640x360) and nearest-upscales it,
+/// so the pixel-snapped look stays the same at any window size. The scene underneath stays
+/// at native resolution.
+///
+/// Every effect pixel covers one virtual pixel, so pixel-sized settings such as
+/// `streak_length_px` and `mask_thickness_px` are measured in virtual pixels. The streak
+/// mask is rendered into a target of this size by the [`RainGlareCompute`] prepass, which
+/// runs for these cameras even while that resource is absent, and the main pass
+/// nearest-samples it. Devices without compute shaders evaluate the mask once per virtual
+/// pixel in the main pass instead. Cameras without it render at native resolution.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct RainGlareVirtualResolution(pub UVec2);
+
+/// Keeps `wind` fixed in screen space for stylized cameras, instead of rotating it with the
+/// camera's roll so rain falls towards world down.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareIgnoreRoll;
+
+/// Per-camera override for the clock that `advance_rain_time` writes into
+/// [`RainGlareSettings::time`]. Cameras without it use the shared elapsed time.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub enum RainGlareTimeSource {
+    /// Use this exact time value (seconds), e.g. driven by a replay timeline.
+    Manual(f32),
+    /// Multiply the shared elapsed time by this factor (e.g. `0.25` for slow motion).
+    Scaled(f32),
+}
+
+/// Scales each camera's effect by the scene's [`AmbientLight`] so it reads consistently in
+/// dark and bright scenes. Off by default.
+///
+/// The scale is written to [`RainGlareDerived::exposure_compensation`] rather than into
+/// `intensity`, so user-set intensities are never overwritten or compounded.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareExposureCompensation(pub bool);
+
+impl RainGlareExposureCompensation {
+    /// Ambient brightness at which the effect is left unscaled (Bevy's default ambient).
+    pub const REFERENCE_BRIGHTNESS: f32 = 80.0;
+    /// How strongly the scale follows ambient brightness (0 = not at all, 1 = linearly).
+    pub const RESPONSE: f32 = 0.25;
+    /// Bounds on the resulting multiplier.
+    pub const MIN_SCALE: f32 = 0.25;
+    pub const MAX_SCALE: f32 = 4.0;
+
+    /// Intensity multiplier for a given ambient brightness: below the reference the few
+    /// highlights are dimmed, above it the effect is boosted.
+    pub fn scale_for(brightness: f32) -> f32 {
+        (brightness.max(0.0) / Self::REFERENCE_BRIGHTNESS)
+            .powf(Self::RESPONSE)
+            .clamp(Self::MIN_SCALE, Self::MAX_SCALE)
+    }
+}
+
+/// Time of day (0..1, wrapping: 0 = midnight, 0.5 = noon) from a day/night cycle, mapped
+/// through [`RainGlareTimeOfDayCurve`] onto every camera's
+/// [`RainGlareDerived::daylight_tint`] and [`RainGlareDerived::daylight_intensity`].
+///
+/// Without it both stay neutral, so authored settings are never touched.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareTimeOfDay(pub f32);
+
+/// How the rain looks at a given time of day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RainGlareDaylight {
+    /// Color multiplier on the streaks, as sRGB like Bevy's other color inputs; converted
+    /// to linear before it reaches the shader. Alpha is ignored.
+    pub tint: Srgba,
+    /// Multiplier on `intensity`.
+    pub intensity: f32,
+}
+
+impl RainGlareDaylight {
+    /// `tint` in linear RGB, as packed into [`RainGlareDerived::daylight_tint`].
+    pub fn linear_tint(&self) -> Vec3 {
+        linear_rgb(self.tint)
+    }
+}
+
+/// The RGB part of an sRGB color in linear space, as the shader multiplies with it.
+fn linear_rgb(color: Srgba) -> Vec3 {
+    let linear = LinearRgba::from(color);
+    Vec3::new(linear.red, linear.green, linear.blue)
+}
+
+/// Maps [`RainGlareTimeOfDay`] to a [`RainGlareDaylight`]. Insert your own to override the
+/// default curve ([`Self::default_curve`]).
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct RainGlareTimeOfDayCurve(pub fn(f32) -> RainGlareDaylight);
+
+impl Default for RainGlareTimeOfDayCurve {
+    fn default() -> Self {
+        Self(Self::default_curve)
+    }
+}
+
+impl RainGlareTimeOfDayCurve {
+    /// Streaks at night, relative to noon.
+    pub const NIGHT: RainGlareDaylight = RainGlareDaylight {
+        tint: Srgba::rgb(0.8, 0.87, 1.0),
+        intensity: 0.45,
+    };
+
+    /// Neutral through the day, easing to the darker, cooler [`Self::NIGHT`] look around
+    /// dusk and dawn.
+    pub fn default_curve(time_of_day: f32) -> RainGlareDaylight {
+        // Sun height: 1 at noon, -1 at midnight.
+        let sun = -(time_of_day * std::f32::consts::TAU).cos();
+        let t = ((sun + 0.2) / 0.5).clamp(0.0, 1.0);
+        let day = t * t * (3.0 - 2.0 * t);
+        let towards_day = |night: f32| night + (1.0 - night) * day;
+        let night = Self::NIGHT.tint;
+        RainGlareDaylight {
+            tint: Srgba::rgb(
+                towards_day(night.red),
+                towards_day(night.green),
+                towards_day(night.blue),
+            ),
+            intensity: towards_day(Self::NIGHT.intensity),
+        }
+    }
+}
+
+/// Fixed-size ring of a camera's most recent [`RainGlareSettings`], for tracking down
+/// flicker and time/sync issues.
+///
+/// Recorded once per frame in `PostUpdate`, after all `Update` systems have touched the
+/// settings, and only in debug builds. Cameras without the component cost nothing.
+#[derive(Component, Clone)]
+pub struct RainGlareHistory {
+    entries: [RainGlareSettings; Self::CAPACITY],
+    next: usize,
+    len: usize,
+}
+
+impl Default for RainGlareHistory {
+    fn default() -> Self {
+        Self {
+            entries: [RainGlareSettings::default(); Self::CAPACITY],
+            next: 0,
+            len: 0,
+        }
+    }
+}
+
+impl RainGlareHistory {
+    /// Number of frames kept; older entries are overwritten.
+    pub const CAPACITY: usize = 120;
+
+    pub fn push(&mut self, settings: RainGlareSettings) {
+        self.entries[self.next] = settings;
+        self.next = (self.next + 1) % Self::CAPACITY;
+        self.len = (self.len + 1).min(Self::CAPACITY);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Recorded settings, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &RainGlareSettings> {
+        let start = (self.next + Self::CAPACITY - self.len) % Self::CAPACITY;
+        (0..self.len).map(move |i| &self.entries[(start + i) % Self::CAPACITY])
+    }
+
+    /// One line per recorded frame, oldest first, indexed relative to the newest (`-0`).
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for (i, settings) in self.iter().enumerate() {
+            out.push_str(&format!("-{}: {settings:?}\n", self.len - 1 - i));
+        }
+        out
+    }
+}
+
+/// Photo-mode marker: holds [`RainGlareSettings::time`] at its current value so the rain
+/// pattern stops animating, while `view_angle_factor` keeps following the camera.
+///
+/// Removing it resumes from the live clock (or [`RainGlareTimeSource`]).
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareFreeze;
+
+/// Damps hitches in the rain animation by feeding `advance_rain_time` an exponential
+/// moving average of the frame delta instead of the raw clock.
+///
+/// The value is the smoothing strength in 0..1: 0 (the default) uses the engine clock
+/// directly, values towards 1 spread a long frame over more of the following frames. The
+/// average rate is preserved, so the smoothed clock tracks real time apart from hitches.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareSmoothing(pub f32);
+
+/// Steps the rain animation at a fixed rate in Hz instead of every frame, e.g. 30 to save
+/// power on laptops while the game runs at 60 fps. The pass still draws every frame,
+/// showing the last stepped pattern; camera-derived fields keep updating every frame.
+///
+/// 0 (the default) advances `time` continuously. Applies to the shared clock (including
+/// [`RainGlareNetworkTime`]) before a camera's [`RainGlareTimeSource`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareUpdateHz(pub f32);
+
+/// Authoritative clock in seconds (e.g. the server's time in a multiplayer game) that drives
+/// the rain animation instead of the local elapsed time, so every client shows the same
+/// pattern at the same moment. Update it every frame from the networking layer.
+///
+/// Takes precedence over [`RainGlareSmoothing`]; a camera's [`RainGlareTimeSource`] and
+/// [`RainGlareFreeze`] still apply on top. Without the resource the local clock is used.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareNetworkTime(pub f64);
+
+impl RainGlareNetworkTime {
+    /// Length of the epochs the clock is split into before it is narrowed to the `f32`
+    /// uniform: one hour, like [`Time::elapsed_seconds_wrapped`]. Epochs start at multiples
+    /// of this, so all clients agree on them; the pattern jumps once at each boundary.
+    pub const EPOCH_SECONDS: f64 = 3600.0;
+
+    /// Seconds since the start of the current epoch. Narrowing only this keeps sub-millisecond
+    /// precision however large the server time grows.
+    pub fn epoch_seconds(&self) -> f32 {
+        self.0.rem_euclid(Self::EPOCH_SECONDS) as f32
+    }
+}
+
+/// Hard limits applied to every camera's settings before they reach the GPU.
+///
+/// `max_intensity` caps the uploaded `intensity` (default
+/// [`RainGlareSettings::MAX_INTENSITY`]), as very high values saturate bloom and tonemapping
+/// downstream. Like [`RainGlareQuality`] the main-world component is left as authored; raise
+/// the cap to deliberately allow more extreme values.
+///
+/// `clamp_thickness` caps the uploaded `mask_thickness_px` at
+/// [`RainGlareSettings::MAX_THICKNESS_RATIO`] times `streak_length_px`, so streaks can't
+/// turn into blobs. Off by default, as blobs may be deliberate.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareLimits {
+    pub max_intensity: f32,
+    pub clamp_thickness: bool,
+}
+
+impl Default for RainGlareLimits {
+    fn default() -> Self {
+        Self {
+            max_intensity: RainGlareSettings::MAX_INTENSITY,
+            clamp_thickness: false,
+        }
+    }
+}
+
+/// Global quality cap applied on top of every camera's settings, e.g. from a graphics menu
+/// to protect low-end devices.
+///
+/// `max_density` clamps the `rain_density` uploaded to the GPU; the main-world component is
+/// left as authored, so raising the cap restores the original look. The shader treats any
+/// density of 1.0 or more as fully dense, so only caps below 1.0 change the picture.
+///
+/// `mobile` switches the shader to a cheap approximation for mobile GPUs (see
+/// [`Self::MOBILE`]); it is a [pipeline option](RainGlarePipeline#pipeline-options).
+#[derive(Resource, Clone, Copy, Debug, PartialEq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareQuality {
+    pub max_density: f32,
+    pub mobile: bool,
+}
+
+impl Default for RainGlareQuality {
+    fn default() -> Self {
+        Self::HIGH
+    }
+}
+
+impl RainGlareQuality {
+    pub const LOW: Self = Self {
+        max_density: 0.35,
+        mobile: false,
+    };
+    pub const MEDIUM: Self = Self {
+        max_density: 0.7,
+        mobile: false,
+    };
+    /// Uncapped; the default.
+    pub const HIGH: Self = Self {
+        max_density: RainGlareSettings::MAX_DENSITY,
+        mobile: false,
+    };
+    /// For mobile GPUs: the procedural line pattern becomes a single stretched noise lookup,
+    /// streaks take 4 samples instead of 16 and `highlight_bleed_px` is ignored. Same overall
+    /// look, a fraction of the per-pixel work.
+    pub const MOBILE: Self = Self {
+        max_density: 0.35,
+        mobile: true,
+    };
+
+    /// Environment variable read by [`RainGlarePlugin::with_env_quality`].
+    pub const ENV_VAR: &'static str = "RAIN_GLARE_QUALITY";
+
+    /// Preset for `low`, `medium`, `high` or `mobile` (case-insensitive).
+    pub fn from_preset(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Self::LOW),
+            "medium" => Some(Self::MEDIUM),
+            "high" => Some(Self::HIGH),
+            "mobile" => Some(Self::MOBILE),
+            _ => None,
+        }
+    }
+}
+
+/// Source of a global rain amount, e.g. a weather simulation, that [`drive_rain_amount`]
+/// maps onto every rain camera.
+///
+/// Implement it for your own resource and add `drive_rain_amount::<YourResource>` to
+/// `Update`; [`RainGlareRainAmount`] is wired up by the plugin.
+pub trait RainGlareDriver: Resource {
+    /// How hard it is raining: 0 is dry, 1 is each camera's authored look.
+    fn rain_amount(&self) -> f32;
+}
+
+/// Simple [`RainGlareDriver`]: insert it to scale every rain camera's intensity and density.
+///
+/// Without it (or another driver) the settings are left untouched. The default, 1, is the
+/// authored look.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareRainAmount(pub f32);
+
+impl Default for RainGlareRainAmount {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl RainGlareDriver for RainGlareRainAmount {
+    fn rain_amount(&self) -> f32 {
+        self.0
+    }
+}
+
+/// Normalized 0..1 beat envelope (e.g. from audio analysis) that pulses every camera's
+/// intensity by its [`RainGlareSettings::beat_response`].
+///
+/// Without it, or with `beat_response` at 0, the intensity is left as authored.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareBeat(pub f32);
+
+/// Nudges every rain camera's `threshold` so roughly `target_coverage` of the screen is
+/// bright enough to streak, estimated from a sparse grid of pixels read back from the GPU.
+///
+/// Off while absent (the default). Estimates arrive a few frames late. The samples keep
+/// their HDR values, so on an HDR camera the threshold can settle above 1.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareAutoThreshold {
+    /// Fraction of the screen (0..1) that should be above `threshold`.
+    pub target_coverage: f32,
+    /// How quickly `threshold` follows the estimate, per second.
+    pub rate: f32,
+}
+
+impl Default for RainGlareAutoThreshold {
+    fn default() -> Self {
+        Self {
+            target_coverage: 0.02,
+            rate: 2.0,
+        }
+    }
+}
+
+/// Rough fraction (0..1) of the screen currently covered by rain streaks, e.g. for blurring
+/// the player's perceived visibility.
+///
+/// Insert it to turn the measurement on; the plugin then updates it from the same sparse GPU
+/// readback as [`RainGlareAutoThreshold`], a few frames late. With several rain cameras it
+/// holds the largest coverage. Off by default, as the readback adds a small pass, a copy and
+/// mapping work every frame.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareCoverage(pub f32);
+
+/// Rain cameras whose effect currently renders: the camera is active and its uniform passes
+/// [`RainGlareUniform::is_active`]. Sorted by entity.
+///
+/// Insert it to have the plugin refresh it in `PostUpdate` each frame, e.g. for editor
+/// panels and HUDs; while absent nothing is tracked.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareActiveCameras(pub Vec<Entity>);
+
+/// Accumulates streak coverage into a persistent per-camera wetness buffer that dries at
+/// each camera's [`RainGlareSettings::dry_rate`], so drops leave lingering wet trails;
+/// streaks are brightened by [`RainGlareSettings::wetness_gain`] where it has built up.
+///
+/// A [pipeline option](RainGlarePipeline#pipeline-options): the buffers cost two
+/// single-channel half-float textures per rain camera, and the pass gets a second color
+/// target, so a custom [`RainGlareEntryPoint`] has to return the same two-target output as
+/// `fragment` under the `WETNESS` shader def.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareWetness;
+
+/// Evaluates the procedural streak mask in a compute prepass, once per pixel per frame,
+/// and has the fragment pass sample it instead of evaluating the mask itself. Can pay off
+/// at very high densities or with expensive mask settings.
+///
+/// A [pipeline option](RainGlarePipeline#pipeline-options), ignored with a warning
+/// on devices without compute shader support (e.g. WebGL2). The prepass binds the same
+/// settings uniform as the main pass.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareCompute;
+
+/// Antialiases the edges of the streak lines (and drops) over each pixel's footprint,
+/// measured with screen-space derivatives, instead of leaving them hard. Edges then stay
+/// smooth but crisp at any resolution or `pattern_scale`, without the fixed width of
+/// [`RainGlareSettings::edge_softness_px`], which still takes over where it is set.
+///
+/// A [pipeline option](RainGlarePipeline#pipeline-options). The compute prepass of
+/// [`RainGlareCompute`] has no derivatives and assumes a footprint of one pattern pixel
+/// instead.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareAnalyticAa;
+
+/// Colors read back from one view's grid, before and after the pass.
+struct ReadbackFrame {
+    scene: Vec<Vec3>,
+    output: Vec<Vec3>,
+}
+
+/// Readback results for [`RainGlareAutoThreshold`] and [`RainGlareCoverage`], keyed by
+/// camera (render-world views share their main-world camera's entity). Shared between both
+/// worlds.
+#[derive(Resource, Clone, Default)]
+struct RainGlareReadbackSamples(Arc<Mutex<HashMap<Entity, ReadbackFrame>>>);
+
+/// Sent in `PostUpdate` for every camera whose [`RainGlareSettings`] changed this frame
+/// (including when they were added), carrying a snapshot of the new values.
+///
+/// `time` and `view_angle_factor` are ignored when comparing, as the auto-time system
+/// writes them every frame; their values in the snapshot are just the current ones.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RainGlareSettingsChanged {
+    pub entity: Entity,
+    pub settings: RainGlareSettings,
+}
+
+/// Eases `rain_density` from 0 up to a target when rain starts, then removes itself.
+///
+/// Insert it alongside (or after) [`RainGlareSettings`]; the camera's density is driven from
+/// 0 on the first update, so there is no need to zero it beforehand.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct RainGlareRamp {
+    pub target_density: f32,
+    /// Ramp length in seconds.
+    pub duration: f32,
+    #[reflect(ignore)]
+    elapsed: f32,
+}
+
+impl RainGlareRamp {
+    pub fn new(target_density: f32, duration: f32) -> Self {
+        Self {
+            target_density,
+            duration,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/// Eases a camera's [`RainGlareSettings`] towards `target` (e.g. a preset such as
+/// [`RainGlareSettings::DOWNPOUR`]) over `duration` seconds, then removes itself.
+///
+/// The starting point is captured on the first update. Fields written by the plugin's own
+/// systems (`time`, `view_angle_factor`) keep their live values instead of being
+/// interpolated.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct RainGlareTransition {
+    pub target: RainGlareSettings,
+    /// Transition length in seconds.
+    pub duration: f32,
+    /// Send a [`RainGlareTransitionFinished`] event on arrival.
+    pub notify: bool,
+    #[reflect(ignore)]
+    from: Option<RainGlareSettings>,
+    #[reflect(ignore)]
+    elapsed: f32,
+}
+
+impl RainGlareTransition {
+    pub fn new(target: RainGlareSettings, duration: f32) -> Self {
+        Self {
+            target,
+            duration,
+            notify: false,
+            from: None,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Send a [`RainGlareTransitionFinished`] event when the target is reached.
+    pub fn with_event(mut self) -> Self {
+        self.notify = true;
+        self
+    }
+}
+
+/// Sent when a [`RainGlareTransition`] built with [`RainGlareTransition::with_event`]
+/// reaches its target.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RainGlareTransitionFinished {
+    pub entity: Entity,
+}
+
+/// Swaps a camera's [`RainGlareSettings`] for cheaper ones as it gets far from a reference
+/// point, e.g. for a distant security-monitor camera.
+///
+/// The camera's own settings are used while it is closer than every level's distance (and
+/// may be edited as usual there); beyond a level's distance its settings take over. Fields
+/// written by the plugin's own systems keep their live values across swaps, as with
+/// [`RainGlareTransition`]. Cameras without it are never swapped.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct RainGlareLod {
+    pub reference: RainGlareLodReference,
+    /// `(distance, settings)` pairs, sorted by increasing distance.
+    pub levels: Vec<(f32, RainGlareSettings)>,
+    #[reflect(ignore)]
+    near: Option<RainGlareSettings>,
+    #[reflect(ignore)]
+    active: Option<usize>,
+}
+
+impl RainGlareLod {
+    pub fn new(reference: RainGlareLodReference) -> Self {
+        Self {
+            reference,
+            levels: Vec::new(),
+            near: None,
+            active: None,
+        }
+    }
+
+    /// Use `settings` once the camera is at least `distance` away from the reference.
+    pub fn with_level(mut self, distance: f32, settings: RainGlareSettings) -> Self {
+        let index = self.levels.partition_point(|(d, _)| *d <= distance);
+        self.levels.insert(index, (distance, settings));
+        self
+    }
+}
+
+/// What a [`RainGlareLod`] measures the camera's distance to.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum RainGlareLodReference {
+    /// A fixed world-space point.
+    Point(Vec3),
+    /// An entity's global translation, e.g. the player. Nothing is swapped while it is
+    /// missing.
+    Entity(Entity),
+}
+
+/// Optional screen-space mask that suppresses the effect where its alpha is non-zero.
+///
+/// Typically a render-target image written by a UI camera, so opaque HUD panels stay free
+/// of streaks. The mask is sampled with the view's UVs; cameras without it are unaffected.
+/// The pass already runs in `Core3d` before UI rendering, so UI drawn by the same camera
+/// always sits on top of the streaks without needing a mask.
+#[derive(Component, Clone, ExtractComponent, Reflect)]
+#[reflect(Component)]
+pub struct RainGlareExclusionMask(pub Handle<Image>);
+
+/// Developer view that replaces the composited output with an intermediate term; a
+/// [pipeline option](RainGlarePipeline#pipeline-options).
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum RainGlareDebugView {
+    /// Normal composited output.
+    #[default]
+    Off,
+    /// The procedural streak mask in grayscale, without the scene.
+    MaskOnly,
+    /// The streak contribution that would be added on top of the scene, without the scene.
+    StreaksOnly,
+}
+
+impl RainGlareDebugView {
+    fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        match self {
+            Self::Off => vec![],
+            Self::MaskOnly => vec!["DEBUG_MASK_ONLY".into()],
+            Self::StreaksOnly => vec!["DEBUG_STREAKS_ONLY".into()],
+        }
+    }
+}
+
+/// Space the procedural streak pattern is laid out in; a
+/// [pipeline option](RainGlarePipeline#pipeline-options).
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum RainGlareCoordSpace {
+    /// Pattern keyed to physical pixels. Lines are pixel-exact, but resizing the window
+    /// shifts and rescales the whole pattern.
+    #[default]
+    Pixel,
+    /// Pattern keyed to UVs, scaled to a 1080-pixel-high reference. It stays put when the
+    /// window is resized (a wider window only reveals more of it), but lines are no longer
+    /// pixel-exact: `mask_thickness_px`, `edge_softness_px` and `dry_patch_scale` are then
+    /// measured in reference pixels.
+    Uv,
+}
+
+impl RainGlareCoordSpace {
+    fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        match self {
+            Self::Pixel => vec![],
+            Self::Uv => vec!["PATTERN_UV_SPACE".into()],
+        }
+    }
+}
+
+/// Procedural pattern the lens mask is drawn with; a
+/// [pipeline option](RainGlarePipeline#pipeline-options).
+///
+/// Each pattern is its own mask function in the shader. Everything downstream of the mask
+/// (bright-pass sampling, dry patches, wetness) is shared.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum RainGlarePattern {
+    /// Thin streaks running down parallel lines along the fall direction, the original look.
+    #[default]
+    Streaks,
+    /// Scattered round drops that slide slowly down the glass, each leaving a short trail.
+    /// `rain_density` is the fraction of grid cells holding a drop, `streak_spacing` scales
+    /// the cells, and `mask_thickness_px` / `edge_softness_px` set the drop radius (half of
+    /// it) and edge. The tail, sparkle and cross-blur settings don't apply. Also used as is
+    /// with [`RainGlareQuality::mobile`], in place of the cheap streak approximation.
+    Drops,
+}
+
+impl RainGlarePattern {
+    fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        match self {
+            Self::Streaks => vec![],
+            Self::Drops => vec!["PATTERN_DROPS".into()],
+        }
+    }
+}
+
+/// How the pass writes alpha, for views rendering into textures that are composited later;
+/// a [pipeline option](RainGlarePipeline#pipeline-options).
+///
+/// The pass always overwrites the target (no blending) with all channels, alpha included,
+/// so the modes differ only in what they write.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum RainGlareAlphaMode {
+    /// Alpha is passed through unchanged. Right for opaque targets; on transparent pixels
+    /// the streaks' added light is lost when the target is later composited.
+    #[default]
+    Opaque,
+    /// The target holds premultiplied color. Streaks are emitted light added to `rgb`, and
+    /// alpha is raised to at least the brightest channel (up to 1) so the result stays a
+    /// valid premultiplied color: streaks over transparent pixels composite as coverage.
+    Premultiplied,
+    /// Exports the streaks alone as a straight-alpha overlay: `rgb` is the streak color and
+    /// alpha its coverage (0..1), and the scene is dropped. Meant for a camera rendering
+    /// into an image (or one with [`RainGlareCaptureOutput`]) whose result is
+    /// alpha-composited over something else, e.g. a wet-screen UI layer.
+    Coverage,
+}
+
+impl RainGlareAlphaMode {
+    fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        match self {
+            Self::Opaque => vec![],
+            Self::Premultiplied => vec!["ALPHA_PREMULTIPLIED".into()],
+            Self::Coverage => vec!["ALPHA_COVERAGE".into()],
+        }
+    }
+}
+
+/// Fragment entry point of the effect's shader, for shipping several variants (e.g.
+/// `fragment_cheap`, `fragment_fancy`) in one WGSL file and switching between them.
+///
+/// A [pipeline option](RainGlarePipeline#pipeline-options). It must name a `@fragment`
+/// function of the effect's shader as composed with its imports and the current shader
+/// defs; otherwise an error is logged and the default `fragment` is used. It is checked
+/// again whenever a shader changes.
+#[derive(Resource, Clone, Debug, PartialEq, Eq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareEntryPoint(pub String);
+
+impl Default for RainGlareEntryPoint {
+    fn default() -> Self {
+        Self("fragment".into())
+    }
+}
+
+/// Render-world copies of the app's shaders, updated from their asset events, so the effect's
+/// shader can be composed the way the pipeline cache will to check [`RainGlareEntryPoint`].
+#[derive(Resource, Default)]
+struct RainGlareShaders(HashMap<AssetId<Shader>, Shader>);
+
+fn extract_rain_glare_shaders(
+    shaders: Extract<Res<Assets<Shader>>>,
+    mut events: Extract<EventReader<AssetEvent<Shader>>>,
+    mut copies: ResMut<RainGlareShaders>,
+) {
+    for event in events.read() {
+        match *event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(shader) = shaders.get(id) {
+                    copies.0.insert(id, shader.clone());
+                }
+            }
+            AssetEvent::Removed { id } | AssetEvent::Unused { id } => {
+                copies.0.remove(&id);
+            }
+            AssetEvent::LoadedWithDependencies { .. } => {}
+        }
+    }
+}
+
+/// Names of the `@fragment` entry points of `shader` as composed with `shader_defs`, or
+/// `None` while it or one of its imports is missing or it fails to compose.
+fn fragment_entry_points(
+    shaders: &HashMap<AssetId<Shader>, Shader>,
+    shader: AssetId<Shader>,
+    shader_defs: &[ShaderDefVal],
+) -> Option<Vec<String>> {
+    fn add_import(
+        composer: &mut Composer,
+        shaders: &HashMap<AssetId<Shader>, Shader>,
+        import: &ShaderImport,
+    ) -> Option<()> {
+        if composer.contains_module(&import.module_name()) {
+            return Some(());
+        }
+        let shader = shaders
+            .values()
+            .find(|shader| shader.import_path() == import)?;
+        for import in shader.imports() {
+            add_import(composer, shaders, import)?;
+        }
+        composer.add_composable_module(shader.into()).ok()?;
+        Some(())
+    }
+
+    let shader = shaders.get(&shader)?;
+    let mut composer = Composer::default();
+    for import in shader.imports() {
+        add_import(&mut composer, shaders, import)?;
+    }
+    let shader_defs = shader_defs
+        .iter()
+        .chain(&shader.shader_defs)
+        .map(|def| match def.clone() {
+            ShaderDefVal::Bool(key, value) => (key, ShaderDefValue::Bool(value)),
+            ShaderDefVal::Int(key, value) => (key, ShaderDefValue::Int(value)),
+            ShaderDefVal::UInt(key, value) => (key, ShaderDefValue::UInt(value)),
+        })
+        .collect();
+    let module = composer
+        .make_naga_module(NagaModuleDescriptor {
+            shader_defs,
+            ..shader.into()
+        })
+        .ok()?;
+    Some(
+        module
+            .entry_points
+            .into_iter()
+            .filter(|entry_point| entry_point.stage == ShaderStage::Fragment)
+            .map(|entry_point| entry_point.name)
+            .collect(),
+    )
+}
+
+/// Opt-in marker that keeps a copy of the after-glare image for later render graph nodes.
+///
+/// The effect normally writes in place through `post_process_write`, so the result is only
+/// visible as "the current main texture". With this marker on the camera, the render-world
+/// view entity additionally gets a [`RainGlareOutputTexture`] each frame. Downstream nodes
+/// order themselves after [`RainGlareLabel`] and query that component to sample it.
+#[derive(Component, Clone, Copy, Debug, Default, ExtractComponent, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareCaptureOutput;
+
+/// Opt-in marker that keeps the effect inside the camera's [`Camera::viewport`], so
+/// letterbox or pillarbox bars stay untouched.
+///
+/// Without it the fullscreen triangle covers the whole render target, as it always has.
+/// With it, and a viewport smaller than the target, the pass copies the scene across first
+/// and then scissors its draw to the viewport; the plugin adds `COPY_SRC | COPY_DST` to the
+/// camera's [`CameraMainTextureUsages`] for that copy.
+#[derive(Component, Clone, Copy, Debug, Default, ExtractComponent, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareLetterboxAware;
+
+/// Opt-in marker that adapts the streaks to the camera's [`MotionBlur`], which otherwise
+/// smears the scene the streaks sample and makes them look doubled.
+///
+/// While the motion blur node is in the graph and actually blurs this camera (a
+/// [`MotionBlur`] with samples and a non-zero `shutter_angle`, plus motion vectors), the
+/// uploaded settings drop the streaks' own smear (`motion_response`) and dim `intensity` by
+/// up to half, scaling with `shutter_angle` up to 1. The main-world component is left as
+/// authored, and the pass stays where it was in the graph.
+#[derive(Component, Clone, Copy, Debug, Default, ExtractComponent, Reflect)]
+#[reflect(Component, Default)]
+pub struct RainGlareMotionBlurAware;
+
+/// What a camera does when its main texture turns out not to be the expected SDR or HDR
+/// format (e.g. HDR requested on hardware without the usual HDR format).
+///
+/// Cameras without it behave as [`Self::Lenient`].
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, ExtractComponent, Reflect)]
+#[reflect(Component, Default)]
+pub enum RainGlareFormatPolicy {
+    /// Queue a pipeline for the unexpected format and keep the effect.
+    #[default]
+    Lenient,
+    /// Log an error and skip the effect on this camera, to catch format misconfigurations
+    /// early in shipping builds.
+    Strict,
+}
+
+/// Render-world copy of a view's after-glare image, requested with [`RainGlareCaptureOutput`].
+///
+/// Written by the [`RainGlareLabel`] node; it matches the view's main texture size and
+/// format and can be bound as a sampled texture.
+#[derive(Component)]
+pub struct RainGlareOutputTexture {
+    pub texture: CachedTexture,
+}
+
+/// Opt-in timing of the rain glare pass.
+///
+/// With `enabled` set, the pass is wrapped in a Bevy render diagnostics span and the latest
+/// measurements are copied back here every frame. This needs Bevy's
+/// `RenderDiagnosticsPlugin`, which takes care of the GPU readback. GPU time needs the
+/// `TIMESTAMP_QUERY` and `TIMESTAMP_QUERY_INSIDE_PASSES` device features; without them
+/// (Metal, WebGPU, WebGL2) a warning is logged once and only CPU time is reported. Times are
+/// in milliseconds and arrive a few frames late.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareProfiling {
+    pub enabled: bool,
+    /// GPU time of the most recent measured pass.
+    pub gpu_time_ms: Option<f64>,
+    /// CPU time spent encoding the most recent measured pass.
+    pub cpu_time_ms: Option<f64>,
+}
+
+impl RainGlareProfiling {
+    /// Diagnostics store path of the pass's GPU time.
+    pub const GPU_TIME: DiagnosticPath =
+        DiagnosticPath::const_new("render/rain_glare_pass/elapsed_gpu");
+    /// Diagnostics store path of the pass's CPU time.
+    pub const CPU_TIME: DiagnosticPath =
+        DiagnosticPath::const_new("render/rain_glare_pass/elapsed_cpu");
+}
+
+/// Sampler options for the screen texture, e.g. anisotropic filtering to keep streaks crisp
+/// at grazing camera angles.
+///
+/// Read once when the render pipeline is created, so set it before the app runs (insert it
+/// or mutate the one the plugin initializes); later changes have no effect.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Resource, Default)]
+pub struct RainGlareSamplerConfig {
+    /// Maximum anisotropy (1 = off). Values above 1 switch the sampler to linear filtering,
+    /// which anisotropy requires; the backend caps them at what the device supports. On
+    /// adapters without anisotropic filtering (e.g. some WebGL2 and GLES devices) it falls
+    /// back to 1.
+    pub anisotropy: u16,
+}
+
+impl Default for RainGlareSamplerConfig {
+    fn default() -> Self {
+        Self { anisotropy: 1 }
+    }
+}
+
+impl RainGlareSamplerConfig {
+    fn descriptor(&self, adapter: &RenderAdapter) -> SamplerDescriptor<'static> {
+        let supported = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::ANISOTROPIC_FILTERING);
+        if self.anisotropy <= 1 || !supported {
+            return SamplerDescriptor::default();
+        }
+
+        SamplerDescriptor {
+            label: Some("rain_glare_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            anisotropy_clamp: self.anisotropy,
+            ..default()
+        }
+    }
+}
+
+/// Load behavior of the rain glare pass's color attachment.
+///
+/// The fullscreen pass overwrites every pixel, so [`Self::Clear`] is mainly a debugging hook
+/// for isolating the effect or for experiments with shaders that discard or blend.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum RainGlareAttachmentOps {
+    /// Load the destination's existing contents (`Operations::default()`).
+    #[default]
+    Load,
+    /// Clear the destination to the given color before drawing.
+    Clear(LinearRgba),
+}
+
+impl RainGlareAttachmentOps {
+    fn color_attachment<'a>(&self, view: &'a TextureView) -> RenderPassColorAttachment<'a> {
+        let ops = match *self {
+            Self::Load => Operations::default(),
+            Self::Clear(color) => Operations {
+                load: LoadOp::Clear(color.into()),
+                store: StoreOp::Store,
+            },
+        };
+
+        RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops,
+        }
+    }
+}
+
+/// Errors reported by [`RainGlarePlugin`] when the app is not set up for rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RainGlareError {
+    /// The app has no [`RenderApp`] sub-app (e.g. headless or `MinimalPlugins` setups),
+    /// so the effect has nowhere to render.
+    MissingRenderApp,
+}
+
+impl std::fmt::Display for RainGlareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingRenderApp => write!(
+                f,
+                "RainGlarePlugin was added to an app without a RenderApp; \
+                 add `DefaultPlugins` (or `RenderPlugin`) before it, or the effect will never render"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RainGlareError {}
+
+/// Why [`RainGlareSettings::try_new`] or [`RainGlareSettings::validate`] rejected its input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RainGlareSettingsError {
+    /// The field was NaN or infinite.
+    NonFinite { field: &'static str, value: f32 },
+    /// The field was finite but outside `min..=max` (an infinite bound means unbounded).
+    OutOfRange {
+        field: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+impl std::fmt::Display for RainGlareSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::NonFinite { field, value } => {
+                write!(f, "rain glare {field} must be finite, got {value}")
+            }
+            Self::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } if max.is_infinite() => {
+                write!(f, "rain glare {field} must be at least {min}, got {value}")
+            }
+            Self::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "rain glare {field} must be in {min}..={max}, got {value}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RainGlareSettingsError {}
+
+/// Where in the `Core3d` graph the rain glare pass runs, set with
+/// [`RainGlarePlugin::layer_depth`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RainGlareLayerDepth {
+    /// After tonemapping, on top of everything the camera rendered.
+    #[default]
+    AfterTonemapping,
+    /// Between the transmissive and transparent main passes, so glass and other transparent
+    /// geometry draws over the rain (rain "outside the window").
+    ///
+    /// The pass then sees linear, untonemapped color, so `threshold` usually needs raising,
+    /// and it requires `Msaa::Off`: with MSAA the transparent pass resolves over the result.
+    BeforeTransparent,
+    /// After the UI pass, on top of the HUD, for overlay-style rain. The pass then sees the
+    /// UI in its input, so bright UI elements streak as well unless a
+    /// [`RainGlareExclusionMask`] covers them.
+    ///
+    /// Needs `bevy_ui`'s UI pass in the graph (its `UiPlugin` added before this plugin);
+    /// without it the pass falls back to [`Self::AfterTonemapping`] with a warning.
+    OverUi,
+}
+
+/// Registers the [`RainGlareStateGate`] systems for [`RainGlarePlugin::run_in_state`].
+type StateGateSetup = Box<dyn Fn(&mut App) + Send + Sync>;
+
+/// Plugin that wires the rain glare effect into the render graph.
+///
+/// The pass runs once per view: each camera with [`RainGlareSettings`] gets its own
+/// `post_process_write` ping-pong and its own dynamic uniform offset. Stereo/XR setups that
+/// render each eye as a separate camera (the only layout Bevy's pipeline cache supports, as
+/// it always builds pipelines with `multiview: None`) therefore work per eye, provided both
+/// eye cameras carry the settings component. The pattern is laid out over each camera's
+/// [`Camera::viewport`] (see [`RainGlareDerived::viewport`]), so eyes that share one
+/// side-by-side target see the same rain; give them [`RainGlareLetterboxAware`] as well so
+/// each eye's pass stays in its own half. Array-texture multiview targets are not
+/// supported.
+///
+/// The node is only added to the `Core3d` graph and its view query requires the settings
+/// component, so in a 3D scene with a 2D overlay camera only cameras that are both 3D and
+/// carry [`RainGlareSettings`] get rain; the 2D camera is never touched.
+pub struct RainGlarePlugin {
+    require_render_app: bool,
+    auto_time: bool,
+    time_schedule: InternedScheduleLabel,
+    layer_depth: RainGlareLayerDepth,
+    env_quality: bool,
+    hot_reload_path: Option<String>,
+    state_gate: Option<StateGateSetup>,
+}
+
+impl Default for RainGlarePlugin {
+    fn default() -> Self {
+        Self {
+            require_render_app: false,
+            auto_time: true,
+            time_schedule: Update.intern(),
+            layer_depth: RainGlareLayerDepth::AfterTonemapping,
+            env_quality: false,
+            hot_reload_path: None,
+            state_gate: None,
+        }
+    }
+}
+
+impl RainGlarePlugin {
+    /// Panic during [`Plugin::build`] instead of warning when the app has no render sub-app.
+    ///
+    /// Useful when a missing renderer is a configuration bug rather than an intentional
+    /// headless run.
+    pub fn require_render_app(mut self, require: bool) -> Self {
+        self.require_render_app = require;
+        self
+    }
+
+    /// Don't register the system that drives `time` and `view_angle_factor`.
+    ///
+    /// Both fields are then entirely user-managed; `view_angle_factor` stays at whatever the
+    /// camera was spawned with (1.0 by default), so the effect no longer fades with pitch
+    /// unless you update it yourself.
+    pub fn without_auto_time(mut self) -> Self {
+        self.auto_time = false;
+        self
+    }
+
+    /// Schedule the system driving `time`, `view_angle_factor` and the other camera-derived
+    /// fields runs in (default `Update`).
+    ///
+    /// In `Update` it reads the camera's [`GlobalTransform`] as last propagated, so a
+    /// controller that moves the camera in `PostUpdate` shows up a frame late in the angle
+    /// fade. `PostUpdate` runs it after transform propagation and so sees this frame's
+    /// transform, but then systems in `Update` read last frame's values of those fields.
+    /// In any other schedule it is not ordered against propagation.
+    pub fn time_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.time_schedule = schedule.intern();
+        self
+    }
+
+    /// Choose where in the graph the pass runs; see [`RainGlareLayerDepth`].
+    pub fn layer_depth(mut self, layer_depth: RainGlareLayerDepth) -> Self {
+        self.layer_depth = layer_depth;
+        self
+    }
+
+    /// At startup, replace [`RainGlareQuality`] with the preset named by the
+    /// `RAIN_GLARE_QUALITY` environment variable (`low`, `medium`, `high` or `mobile`; see
+    /// [`RainGlareQuality::from_preset`]), e.g. for benchmarking without code changes. Runs
+    /// in `Startup`, so it wins over a quality resource inserted while building the app.
+    /// Unset leaves the resource alone.
+    pub fn with_env_quality(mut self) -> Self {
+        self.env_quality = true;
+        self
+    }
+
+    /// In debug builds, load the effect's shader from `path` (relative to the asset folder,
+    /// e.g. `"rain_glare.wgsl"`) through the [`AssetServer`] instead of using the embedded
+    /// copy, so edits hot-reload when Bevy's `file_watcher` feature is enabled. Release
+    /// builds ignore it and always use the embedded shader.
+    pub fn with_hot_reload_path(mut self, path: impl Into<String>) -> Self {
+        self.hot_reload_path = Some(path.into());
+        self
+    }
+
+    /// Only run the effect while `state` is the current state of `S`, e.g. in
+    /// `GameState::Playing` but not in menus or cutscenes. Outside it the pass is skipped
+    /// (the camera renders as if the effect weren't there) and `time` stops advancing.
+    ///
+    /// The gate follows `OnEnter` / `OnExit` of `state`, so `S` must be registered with
+    /// `init_state` or `insert_state`; otherwise the effect never runs. Without this the
+    /// effect always runs.
+    pub fn run_in_state<S: States>(mut self, state: S) -> Self {
+        self.state_gate = Some(Box::new(move |app: &mut App| {
+            app.insert_resource(RainGlareStateGate(false))
+                .add_systems(
+                    OnEnter(state.clone()),
+                    |mut gate: ResMut<RainGlareStateGate>| {
+                        gate.0 = true;
+                    },
+                )
+                .add_systems(
+                    OnExit(state.clone()),
+                    |mut gate: ResMut<RainGlareStateGate>| {
+                        gate.0 = false;
+                    },
+                );
+        }));
+        self
+    }
+}
+
+/// Whether the effect may run, per [`RainGlarePlugin::run_in_state`]. In the main world it
+/// only exists while a state is configured; the render world always has it, open unless the
+/// main world's is closed.
+#[derive(Resource)]
+struct RainGlareStateGate(bool);
+
+fn rain_state_gate_open(gate: Option<Res<RainGlareStateGate>>) -> bool {
+    gate.is_none_or(|gate| gate.0)
+}
+
+fn extract_state_gate(
+    main_gate: Extract<Option<Res<RainGlareStateGate>>>,
+    mut gate: ResMut<RainGlareStateGate>,
+) {
+    gate.0 = main_gate.as_ref().is_none_or(|main_gate| main_gate.0);
+}
+
+/// The shader the effect's pipelines are built from: the embedded one, or the file loaded
+/// for [`RainGlarePlugin::with_hot_reload_path`]. Present in both worlds.
+#[derive(Resource, Clone)]
+struct RainGlareShader(Handle<Shader>);
+
+/// Marks that a [`RainGlarePlugin`] instance has already set up the app, with the
+/// configuration that took effect.
+#[derive(Resource)]
+struct RainGlarePluginInstalled(RainGlarePluginConfig);
+
+/// The comparable part of a [`RainGlarePlugin`]'s configuration, to tell apart duplicates
+/// that would have configured the app differently.
+#[derive(Clone, Debug, PartialEq)]
+struct RainGlarePluginConfig {
+    require_render_app: bool,
+    auto_time: bool,
+    time_schedule: InternedScheduleLabel,
+    layer_depth: RainGlareLayerDepth,
+    env_quality: bool,
+    hot_reload_path: Option<String>,
+    state_gate: bool,
+}
+
+impl RainGlarePlugin {
+    fn config(&self) -> RainGlarePluginConfig {
+        RainGlarePluginConfig {
+            require_render_app: self.require_render_app,
+            auto_time: self.auto_time,
+            time_schedule: self.time_schedule,
+            layer_depth: self.layer_depth,
+            env_quality: self.env_quality,
+            hot_reload_path: self.hot_reload_path.clone(),
+            state_gate: self.state_gate.is_some(),
+        }
+    }
+}
+
+impl Plugin for RainGlarePlugin {
+    fn build(&self, app: &mut App) {
+        // Several sub-plugins may each add the effect; only the first instance (and its
+        // configuration) takes effect, later ones are no-ops instead of panicking.
+        if let Some(installed) = app.world().get_resource::<RainGlarePluginInstalled>() {
+            if installed.0 == self.config() {
+                debug!("RainGlarePlugin already added; ignoring duplicate");
+            } else {
+                warn!(
+                    "RainGlarePlugin already added with a different configuration; ignoring \
+                     this instance ({:?}, keeping {:?})",
+                    self.config(),
+                    installed.0
+                );
+            }
+            return;
+        }
+        app.insert_resource(RainGlarePluginInstalled(self.config()));
+
+        load_internal_asset!(
+            app,
+            RAIN_GLARE_TYPES_SHADER_HANDLE,
+            "../assets/rain_glare_types.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            RAIN_GLARE_SHADER_HANDLE,
+            "../assets/rain_glare.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            RAIN_GLARE_READBACK_SHADER_HANDLE,
+            "../assets/rain_glare_readback.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<RainGlareSettings>()
+            .register_type::<RainGlareDerived>()
+            .register_type::<RainGlareTimeSource>()
+            .register_type::<RainGlareFreeze>()
+            .register_type::<RainGlareFadeOut>()
+            .register_type::<RainGlareRamp>()
+            .register_type::<RainGlareTransition>()
+            .register_type::<RainGlareLod>()
+            .register_type::<RainGlareExclusionMask>()
+            .register_type::<RainGlareCaptureOutput>()
+            .register_type::<RainGlareIgnoreRoll>()
+            .register_type::<RainGlareIndoors>()
+            .register_type::<RainGlareWindRelax>()
+            .register_type::<RainGlareVirtualResolution>()
+            .register_type::<RainGlareLetterboxAware>()
+            .register_type::<RainGlareMotionBlurAware>()
+            .register_type::<RainGlareFormatPolicy>()
+            .register_type::<RainGlareBeat>()
+            .register_type::<RainGlareDebugView>()
+            .register_type::<RainGlareCoordSpace>()
+            .register_type::<RainGlarePattern>()
+            .register_type::<RainGlareAlphaMode>()
+            .register_type::<RainGlareEntryPoint>()
+            .register_type::<RainGlareAttachmentOps>()
+            .register_type::<RainGlareExposureCompensation>()
+            .register_type::<RainGlareSmoothing>()
+            .register_type::<RainGlareUpdateHz>()
+            .register_type::<RainGlareNetworkTime>()
+            .register_type::<RainGlareProfiling>()
+            .register_type::<RainGlareQuality>()
+            .register_type::<RainGlareLimits>()
+            .register_type::<RainGlareRainAmount>()
+            .register_type::<RainGlareAutoThreshold>()
+            .register_type::<RainGlareCoverage>()
+            .register_type::<RainGlareActiveCameras>()
+            .register_type::<RainGlareWetness>()
+            .register_type::<RainGlareCompute>()
+            .register_type::<RainGlareAnalyticAa>()
+            .register_type::<RainGlareTimeOfDay>()
+            .register_type::<RainGlareSamplerConfig>();
+
+        app.add_plugins((
+            (
+                ExtractComponentPlugin::<RainGlareSettings>::default(),
+                ExtractComponentPlugin::<RainGlareExclusionMask>::default(),
+                ExtractComponentPlugin::<RainGlareCaptureOutput>::default(),
+                ExtractComponentPlugin::<RainGlareLetterboxAware>::default(),
+                ExtractComponentPlugin::<RainGlareFormatPolicy>::default(),
+                ExtractComponentPlugin::<RainGlareMotionBlurAware>::default(),
+            ),
+            UniformComponentPlugin::<RainGlareUniform>::default(),
+            ExtractResourcePlugin::<RainGlareAttachmentOps>::default(),
+            ExtractResourcePlugin::<RainGlareDebugView>::default(),
+            ExtractResourcePlugin::<RainGlareCoordSpace>::default(),
+            ExtractResourcePlugin::<RainGlarePattern>::default(),
+            ExtractResourcePlugin::<RainGlareAlphaMode>::default(),
+            ExtractResourcePlugin::<RainGlareEntryPoint>::default(),
+            ExtractResourcePlugin::<RainGlareProfiling>::default(),
+            ExtractResourcePlugin::<RainGlareQuality>::default(),
+            ExtractResourcePlugin::<RainGlareLimits>::default(),
+        ))
+        .init_resource::<RainGlareAttachmentOps>()
+        .init_resource::<RainGlareDebugView>()
+        .init_resource::<RainGlareCoordSpace>()
+        .init_resource::<RainGlarePattern>()
+        .init_resource::<RainGlareAlphaMode>()
+        .init_resource::<RainGlareEntryPoint>()
+        .init_resource::<RainGlareExposureCompensation>()
+        .init_resource::<RainGlareSmoothing>()
+        .init_resource::<RainGlareUpdateHz>()
+        .init_resource::<RainGlareProfiling>()
+        .init_resource::<RainGlareQuality>()
+        .init_resource::<RainGlareLimits>()
+        .init_resource::<RainGlareSamplerConfig>()
+        .init_resource::<RainGlareTimeOfDayCurve>()
+        .add_event::<RainGlareSettingsChanged>()
+        .add_event::<RainGlareTransitionFinished>();
+
+        app.add_systems(
+            Update,
+            (
+                select_rain_lod.before(transition_rain_settings),
+                transition_rain_settings.before(ramp_rain_density),
+                ramp_rain_density,
+                fade_out_rain.after(ramp_rain_density),
+                fade_indoors,
+                relax_rain_wind,
+                apply_virtual_resolution,
+                drive_rain_beat,
+                compensate_rain_exposure,
+                apply_time_of_day,
+                read_rain_glare_profiling,
+                warn_sky_only_without_depth,
+                drive_rain_amount::<RainGlareRainAmount>,
+                request_letterbox_usage,
+                dry_rain_wetness,
+                apply_rain_readbacks,
+            ),
+        );
+
+        app.add_systems(PreUpdate, add_rain_glare_derived);
+
+        app.add_systems(
+            PostUpdate,
+            (
+                send_rain_settings_changed,
+                update_active_rain_cameras,
+                apply_camera_viewport.after(CameraUpdateSystem),
+            ),
+        );
+
+        #[cfg(debug_assertions)]
+        app.add_systems(PostUpdate, (record_rain_history, warn_blob_streaks));
+
+        if self.env_quality {
+            app.add_systems(Startup, apply_env_quality);
+        }
+
+        if let Some(state_gate) = &self.state_gate {
+            state_gate(app);
+        }
+
+        if self.auto_time {
+            // Keep the time parameter in sync with the engine clock.
+            let system = advance_rain_time.run_if(rain_state_gate_open);
+            if self.time_schedule == PostUpdate.intern() {
+                app.add_systems(
+                    PostUpdate,
+                    system.after(TransformSystem::TransformPropagate),
+                );
+            } else {
+                app.add_systems(self.time_schedule, system);
+            }
+        }
+
+        let readback_samples = RainGlareReadbackSamples::default();
+        app.insert_resource(readback_samples.clone());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            let error = RainGlareError::MissingRenderApp;
+            if self.require_render_app {
+                panic!("{error}");
+            }
+            warn!("{error}");
+            return;
+        };
+
+        render_app
+            .insert_resource(readback_samples)
+            .init_resource::<RainGlareReadbacks>()
+            .init_resource::<RainGlareWetnessTargets>()
+            .init_resource::<RainGlareComputeEnabled>()
+            .init_resource::<RainGlareComputeSupported>()
+            .init_resource::<RainGlareShaders>()
+            .init_resource::<RainGlareAnalyticAaEnabled>()
+            .insert_resource(RainGlareStateGate(true))
+            .add_systems(
+                ExtractSchedule,
+                (
+                    extract_readback_flags,
+                    extract_wetness_flag,
+                    extract_compute_flag,
+                    check_profiling_support,
+                    extract_analytic_aa_flag,
+                    extract_state_gate,
+                    extract_rain_glare_shaders,
+                ),
+            );
+
+        render_app.add_systems(
+            Render,
+            (
+                prepare_rain_glare_pipelines.in_set(RenderSet::Prepare),
+                queue_view_format_pipelines
+                    .in_set(RenderSet::Prepare)
+                    .after(prepare_rain_glare_pipelines),
+                clamp_rain_density
+                    .in_set(RenderSet::Prepare)
+                    .before(RenderSet::PrepareResources),
+                clamp_pattern_scale
+                    .in_set(RenderSet::Prepare)
+                    .before(RenderSet::PrepareResources),
+                compensate_motion_blur
+                    .in_set(RenderSet::Prepare)
+                    .before(apply_rain_limits),
+                apply_rain_limits
+                    .in_set(RenderSet::Prepare)
+                    .before(RenderSet::PrepareResources),
+                prepare_rain_glare_output_textures.in_set(RenderSet::PrepareResources),
+                prepare_rain_readbacks.in_set(RenderSet::PrepareResources),
+                prepare_rain_wetness.in_set(RenderSet::PrepareResources),
+                prepare_rain_mask_textures.in_set(RenderSet::PrepareResources),
+                map_rain_readbacks.in_set(RenderSet::Cleanup),
+            ),
+        );
+
+        wire_rain_glare_node(render_app, self.layer_depth);
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+
+    fn finish(&self, app: &mut App) {
+        // Only the first instance's `finish` runs, as with `build`.
+        if app.world().contains_resource::<RainGlareShader>() {
+            return;
+        }
+
+        let sampler_config = app
+            .world()
+            .get_resource::<RainGlareSamplerConfig>()
+            .copied()
+            .unwrap_or_default();
+
+        let shader = match &self.hot_reload_path {
+            Some(path) if cfg!(debug_assertions) => {
+                RainGlareShader(app.world().resource::<AssetServer>().load(path.clone()))
+            }
+            _ => RainGlareShader(RAIN_GLARE_SHADER_HANDLE),
+        };
+        app.insert_resource(shader.clone());
+
+        // `build` has already reported a missing render app.
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .insert_resource(sampler_config)
+            .insert_resource(shader)
+            .init_resource::<RainGlareDebugView>()
+            .init_resource::<RainGlareCoordSpace>()
+            .init_resource::<RainGlarePattern>()
+            .init_resource::<RainGlareAlphaMode>()
+            .init_resource::<RainGlareEntryPoint>()
+            .init_resource::<RainGlareQuality>()
+            .init_resource::<RainGlareLimits>()
+            .init_resource::<RainGlarePipeline>();
+    }
+}
+
+/// Adds the rain glare node to `sub_graph`, running after `before` and before `after`.
+///
+/// [`RainGlarePlugin`] already wires it into `Core3d`; use this on the render sub-app (after
+/// adding the plugin) to also get rain in custom sub-graphs such as mirror or portal views.
+/// Those views need the same components as a `Core3d` camera: a `ViewTarget` and a
+/// [`RainGlareUniform`]. The node is labeled [`RainGlareLabel`] in every sub-graph.
+pub fn add_rain_glare_node(
+    render_app: &mut SubApp,
+    sub_graph: impl RenderSubGraph,
+    before: impl RenderLabel,
+    after: impl RenderLabel,
+) {
+    let sub_graph = sub_graph.intern();
+    render_app
+        .add_render_graph_node::<ViewNodeRunner<RainGlareNode>>(sub_graph, RainGlareLabel)
+        .add_render_graph_edges(sub_graph, (before, RainGlareLabel, after));
+}
+
+/// Adds the rain glare node to `Core3d` at `layer_depth`.
+fn wire_rain_glare_node(render_app: &mut SubApp, layer_depth: RainGlareLayerDepth) {
+    match layer_depth {
+        RainGlareLayerDepth::AfterTonemapping => add_rain_glare_node(
+            render_app,
+            Core3d,
+            Node3d::Tonemapping,
+            Node3d::EndMainPassPostProcessing,
+        ),
+        RainGlareLayerDepth::BeforeTransparent => add_rain_glare_node(
+            render_app,
+            Core3d,
+            Node3d::MainTransmissivePass,
+            Node3d::MainTransparentPass,
+        ),
+        RainGlareLayerDepth::OverUi => {
+            let has_ui_pass = render_app
+                .world()
+                .resource::<RenderGraph>()
+                .get_sub_graph(Core3d)
+                .is_some_and(|core_3d| core_3d.get_node_state(NodeUi::UiPass).is_ok());
+            if has_ui_pass {
+                add_rain_glare_node(render_app, Core3d, NodeUi::UiPass, Node3d::Upscaling);
+            } else {
+                warn!(
+                    "rain glare: RainGlareLayerDepth::OverUi needs the UI pass in the graph; \
+                     running after tonemapping instead"
+                );
+                add_rain_glare_node(
+                    render_app,
+                    Core3d,
+                    Node3d::Tonemapping,
+                    Node3d::EndMainPassPostProcessing,
+                );
+            }
+        }
+    }
+}
+
+// Cameras with `is_active: false` are never extracted as views, so they have no
+// `ViewTarget` and neither this node nor the per-view prepare systems run for them.
+#[derive(Default)]
+struct RainGlareNode;
+
+/// Render graph label of the rain glare node in `Core3d`, for ordering custom nodes around it.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct RainGlareLabel;
+
+impl ViewNode for RainGlareNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static RainGlareUniform,
+        &'static DynamicUniformIndex<RainGlareUniform>,
+        Option<&'static RainGlareExclusionMask>,
+        Option<&'static RainGlareOutputTexture>,
+        Option<&'static ViewPrepassTextures>,
+        Option<&'static ExtractedCamera>,
+        Has<RainGlareLetterboxAware>,
+        Option<&'static RainGlareFormatPolicy>,
+        Option<&'static RainGlareMaskTexture>,
+    );
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (
+            view_target,
+            settings,
+            settings_index,
+            exclusion_mask,
+            output_texture,
+            prepass_textures,
+            camera,
+            letterbox_aware,
+            format_policy,
+            mask_texture,
+        ): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if !world.resource::<RainGlareStateGate>().0 {
+            return Ok(());
+        }
+
+        let pipeline = world.resource::<RainGlarePipeline>();
+
+        // Nothing to draw, e.g. the camera is fully indoors: leave the view alone, but still
+        // hand a captured output downstream. Wetness has to keep drying, so that pass runs.
+        if !settings.is_active() && !pipeline.wetness {
+            if let Some(output) = output_texture {
+                copy_to_output(render_context, view_target.main_texture(), output);
+            }
+            return Ok(());
+        }
+
+        let view_format = view_target.main_texture_format();
+
+        // Another (lenient) view may have queued this format; strict views still skip it.
+        if format_policy == Some(&RainGlareFormatPolicy::Strict)
+            && !RainGlarePipeline::expected_formats().contains(&view_format)
+        {
+            return Ok(());
+        }
+
+        let Some(pipeline_id) = pipeline.pipeline_for_format(view_format) else {
+            return Ok(());
+        };
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(render_pipeline) = pipeline_cache.get_render_pipeline(*pipeline_id) else {
+            return Ok(());
+        };
+
+        // A wetness pipeline needs this view's buffers as its second target.
+        let wetness = if pipeline.wetness {
+            let targets = world.resource::<RainGlareWetnessTargets>();
+            let Some(target) = targets.views.get(&graph.view_entity()) else {
+                return Ok(());
+            };
+            Some(target)
+        } else {
+            None
+        };
+
+        // The main pass samples the mask this view's prepass writes, which a compute-mask
+        // pipeline can't do without.
+        let mask_prepass = match mask_texture {
+            Some(mask_texture) => {
+                let Some(compute_pipeline) = pipeline
+                    .compute_pipeline
+                    .and_then(|id| pipeline_cache.get_compute_pipeline(id))
+                else {
+                    return Ok(());
+                };
+                Some((mask_texture, compute_pipeline))
+            }
+            None if pipeline.compute => return Ok(()),
+            None => None,
+        };
+
+        // Each view's `DynamicUniformIndex` is written by `UniformComponentPlugin` together with
+        // that same entity's settings, so the offset always addresses this camera's values no
+        // matter how many cameras share the buffer. Guard against it pointing past the end.
+        let settings_uniforms = world.resource::<ComponentUniforms<RainGlareUniform>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+        let in_bounds = settings_uniforms.uniforms().buffer().is_some_and(|buffer| {
+            u64::from(settings_index.index()) + RainGlareUniform::min_size().get() <= buffer.size()
+        });
+        if !in_bounds {
+            error_once!(
+                "rain glare settings offset {} is outside the uniform buffer; skipping the view",
+                settings_index.index()
+            );
+            return Ok(());
+        }
+
+        // Without a (loaded) mask, bind transparent black so nothing is excluded.
+        let exclusion_view = exclusion_mask
+            .and_then(|mask| world.resource::<RenderAssets<GpuImage>>().get(&mask.0))
+            .map_or(
+                &world.resource::<FallbackImageZero>().texture_view,
+                |image| &image.texture_view,
+            );
+
+        // Zero depth reads as "far plane everywhere", so `sky_only` has no effect without a
+        // (single-sampled) depth prepass.
+        let depth_view = prepass_textures
+            .and_then(|textures| textures.depth.as_ref())
+            .filter(|depth| depth.texture.texture.sample_count() == 1)
+            .map_or(
+                &world.resource::<FallbackImageZero>().texture_view,
+                |depth| &depth.texture.default_view,
+            );
+        // Zero motion means no smear, so `motion_response` is a no-op without the prepass.
+        let motion_view = prepass_textures
+            .and_then(|textures| textures.motion_vectors.as_ref())
+            .filter(|motion| motion.texture.texture.sample_count() == 1)
+            .map_or(
+                &world.resource::<FallbackImageZero>().texture_view,
+                |motion| &motion.texture.default_view,
+            );
+        // Zero alpha keeps the screen-space direction, so `normal_follow` is a no-op without
+        // the prepass.
+        let normal_view = prepass_textures
+            .and_then(|textures| textures.normal.as_ref())
+            .filter(|normal| normal.texture.texture.sample_count() == 1)
+            .map_or(
+                &world.resource::<FallbackImageZero>().texture_view,
+                |normal| &normal.texture.default_view,
+            );
+
+        // Sample the grid before and after the streaks for `RainGlareAutoThreshold` and
+        // `RainGlareCoverage`.
+        let readbacks = world.resource::<RainGlareReadbacks>();
+        let readback = readbacks
+            .views
+            .get(&graph.view_entity())
+            .filter(|readback| readback.ready())
+            .zip(readbacks.pipeline.as_ref())
+            .and_then(|(readback, (layout, id))| {
+                let pipeline = pipeline_cache.get_render_pipeline(*id)?;
+                Some((readback, layout, pipeline))
+            });
+        if let Some((readback, layout, readback_pipeline)) = readback {
+            readback.gather_grid(
+                render_context,
+                layout,
+                readback_pipeline,
+                view_target.main_texture_view(),
+                0,
+            );
+        }
+
+        // Scissor to the viewport for `RainGlareLetterboxAware`, once the copy usages are in.
+        let source_texture = view_target.main_texture().clone();
+        let scissor = camera
+            .filter(|_| letterbox_aware)
+            .and_then(|camera| camera.viewport.as_ref())
+            .and_then(|viewport| letterbox_scissor(viewport, source_texture.size()))
+            .filter(|_| {
+                source_texture
+                    .usage()
+                    .contains(TextureUsages::COPY_SRC | TextureUsages::COPY_DST)
+            });
+
+        let attachment_ops = world.resource::<RainGlareAttachmentOps>();
+        let profiling = world.resource::<RainGlareProfiling>().enabled;
+        let diagnostics = render_context.diagnostic_recorder();
+        let post_process = view_target.post_process_write();
+
+        if scissor.is_some() {
+            // The bars outside the scissor are never drawn, so carry the scene over first.
+            render_context.command_encoder().copy_texture_to_texture(
+                source_texture.as_image_copy(),
+                view_target.main_texture().as_image_copy(),
+                source_texture.size(),
+            );
+        }
+
+        let bind_group = render_context.render_device().create_bind_group(
+            "rain_glare_bind_group",
+            &pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &pipeline.sampler,
+                settings_binding.clone(),
+                exclusion_view,
+                depth_view,
+                motion_view,
+                normal_view,
+                wetness.map_or(
+                    &world.resource::<FallbackImageZero>().texture_view,
+                    WetnessTarget::read_view,
+                ),
+                mask_prepass.map_or(
+                    &world.resource::<FallbackImageZero>().texture_view,
+                    |(mask, _)| &mask.0.default_view,
+                ),
+            )),
+        );
+
+        if let Some((mask, compute_pipeline)) = mask_prepass {
+            let mask_bind_group = render_context.render_device().create_bind_group(
+                "rain_glare_mask_bind_group",
+                &pipeline.compute_layout,
+                &BindGroupEntries::with_indices((
+                    (2, settings_binding.clone()),
+                    (9, &mask.0.default_view),
+                )),
+            );
+
+            let size = mask.0.texture.size();
+            let mut compute_pass =
+                render_context
+                    .command_encoder()
+                    .begin_compute_pass(&ComputePassDescriptor {
+                        label: Some("rain_glare_mask_pass"),
+                        timestamp_writes: None,
+                    });
+            compute_pass.set_pipeline(compute_pipeline);
+            compute_pass.set_bind_group(0, &mask_bind_group, &[settings_index.index()]);
+            compute_pass.dispatch_workgroups(
+                size.width.div_ceil(MASK_WORKGROUP_SIZE),
+                size.height.div_ceil(MASK_WORKGROUP_SIZE),
+                1,
+            );
+        }
+
+        let mut color_attachments = vec![Some(
+            attachment_ops.color_attachment(post_process.destination),
+        )];
+        if let Some(target) = wetness {
+            color_attachments.push(Some(RenderPassColorAttachment {
+                view: target.write_view(),
+                resolve_target: None,
+                ops: Operations::default(),
+            }));
+        }
+
+        // Scoped so the pass (and its profiling span) release `render_context` before the
+        // copies below.
+        {
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("rain_glare_pass"),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let pass_span =
+                profiling.then(|| diagnostics.pass_span(&mut render_pass, "rain_glare_pass"));
+
+            render_pass.set_render_pipeline(render_pipeline);
+            if let Some(URect { min, max }) = scissor {
+                render_pass.set_scissor_rect(min.x, min.y, max.x - min.x, max.y - min.y);
+            }
+            render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+            render_pass.draw(0..3, 0..1);
+
+            if let Some(pass_span) = pass_span {
+                pass_span.end(&mut render_pass);
+            }
+        }
+
+        if let Some(output) = output_texture {
+            // `post_process_write` flipped the main texture, so it is now our destination.
+            copy_to_output(render_context, view_target.main_texture(), output);
+        }
+
+        if let Some((readback, layout, readback_pipeline)) = readback {
+            readback.gather_grid(
+                render_context,
+                layout,
+                readback_pipeline,
+                view_target.main_texture_view(),
+                1,
+            );
+            readback.copy_grid(render_context.command_encoder());
+            readback.state.store(READBACK_COPIED, Ordering::Release);
+        }
+
+        Ok(())
+    }
+}
+
+/// Copies the view's current main texture into its [`RainGlareOutputTexture`].
+fn copy_to_output(
+    render_context: &mut RenderContext,
+    source: &Texture,
+    output: &RainGlareOutputTexture,
+) {
+    render_context.command_encoder().copy_texture_to_texture(
+        source.as_image_copy(),
+        output.texture.texture.as_image_copy(),
+        source.size(),
+    );
+}
+
+/// The part of a `size` target covered by `viewport`, or `None` when that is the whole
+/// target (or nothing), in which case scissoring would not change anything.
+fn letterbox_scissor(viewport: &Viewport, size: Extent3d) -> Option<URect> {
+    let target = UVec2::new(size.width, size.height);
+    let min = viewport.physical_position.min(target);
+    let max = (viewport.physical_position + viewport.physical_size).min(target);
+    if min.cmpge(max).any() || (min == UVec2::ZERO && max == target) {
+        return None;
+    }
+    Some(URect { min, max })
+}
+
+/// Render-world resource holding the effect's bind group layout, sampler and per-format
+/// pipelines, exposed read-only for companion passes that want to reuse them.
+///
+/// # Pipeline options
+///
+/// [`RainGlareDebugView`], [`RainGlareCoordSpace`], [`RainGlarePattern`],
+/// [`RainGlareAlphaMode`], [`RainGlareEntryPoint`], [`RainGlareQuality::mobile`],
+/// [`RainGlareWetness`], [`RainGlareCompute`] and [`RainGlareAnalyticAa`] select shader
+/// defs or pipeline state, so they are app-wide resources rather than per-camera settings.
+/// The plugin initializes the enum-like ones to their defaults; the marker resources are
+/// off while absent. Changing, inserting or removing one re-queues the pipelines as
+/// [`Self::rebuild`] does.
+#[derive(Resource)]
+pub struct RainGlarePipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    shader: Handle<Shader>,
+    /// One pipeline per view format. The SDR (`bevy_default`) and HDR formats are queued up
+    /// front; any other format a view turns out to use (e.g. an HDR camera on hardware
+    /// without the usual HDR format) is added on first sight, so the map is bounded by the
+    /// number of distinct view formats. Re-queueing replaces rather than grows it.
+    pipelines: HashMap<TextureFormat, CachedRenderPipelineId>,
+    /// Debug view the current `pipelines` were queued with.
+    debug_view: RainGlareDebugView,
+    /// Pattern space the current `pipelines` were queued with.
+    coord_space: RainGlareCoordSpace,
+    /// Mask pattern the current `pipelines` were queued with.
+    pattern: RainGlarePattern,
+    /// Alpha mode the current `pipelines` were queued with.
+    alpha_mode: RainGlareAlphaMode,
+    /// Fragment entry point the current `pipelines` were queued with: `requested_entry_point`
+    /// if the shader has it, else the default.
+    entry_point: RainGlareEntryPoint,
+    /// The [`RainGlareEntryPoint`] the current `pipelines` were resolved from.
+    requested_entry_point: RainGlareEntryPoint,
+    /// Whether the current `pipelines` were queued with the mobile approximation.
+    mobile: bool,
+    /// Whether the current `pipelines` were queued with the wetness target.
+    wetness: bool,
+    /// Whether the current `pipelines` sample the compute-prepass mask.
+    compute: bool,
+    /// Whether the current `pipelines` antialias the mask edges.
+    analytic_aa: bool,
+    /// Layout of the mask prepass's bind group (settings, mask output).
+    compute_layout: BindGroupLayout,
+    /// The mask prepass, queued while `compute` is set or once a view needs a
+    /// [`RainGlareVirtualResolution`] mask.
+    compute_pipeline: Option<CachedComputePipelineId>,
+}
+
+impl RainGlarePipeline {
+    /// Layout of the effect's bind group (screen texture, sampler, settings, exclusion mask,
+    /// depth, motion vectors, normals, previous wetness, prepass mask), in binding order.
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    /// Filtering sampler used for the screen texture and exclusion mask.
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    /// The queued pipeline for each supported view format.
+    pub fn pipeline_ids(&self) -> impl Iterator<Item = (&TextureFormat, &CachedRenderPipelineId)> {
+        self.pipelines.iter()
+    }
+
+    /// Drops every queued pipeline and queues fresh ones with the current options, e.g. from
+    /// a render-world system after a settings screen changed something the pipelines were
+    /// built from. Changing a [pipeline option](Self#pipeline-options) already does this.
+    ///
+    /// The SDR and HDR formats are re-queued right away and any other view format on next
+    /// sight. Views are skipped, not drawn with a stale pipeline, until the new ones have
+    /// compiled.
+    pub fn rebuild(&mut self, pipeline_cache: &PipelineCache) {
+        self.pipelines.clear();
+        self.queue_pipelines(pipeline_cache);
+    }
+
+    fn pipeline_for_format(&self, format: TextureFormat) -> Option<&CachedRenderPipelineId> {
+        self.pipelines.get(&format)
+    }
+
+    /// The SDR and HDR view formats, whose pipelines are queued up front.
+    fn expected_formats() -> [TextureFormat; 2] {
+        [
+            TextureFormat::bevy_default(),
+            ViewTarget::TEXTURE_FORMAT_HDR,
+        ]
+    }
+
+    /// (Re)queue one pipeline per known view format with the current shader defs.
+    fn queue_pipelines(&mut self, pipeline_cache: &PipelineCache) {
+        let mut formats: Vec<_> = self.pipelines.keys().copied().collect();
+        for format in Self::expected_formats() {
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+
+        for format in formats {
+            self.queue_pipeline(pipeline_cache, format);
+        }
+        self.compute_pipeline = (self.compute || self.compute_pipeline.is_some())
+            .then(|| self.queue_compute_pipeline(pipeline_cache));
+    }
+
+    fn queue_compute_pipeline(&self, pipeline_cache: &PipelineCache) -> CachedComputePipelineId {
+        let mut shader_defs = self.coord_space.shader_defs();
+        shader_defs.extend(self.pattern.shader_defs());
+        if self.mobile {
+            shader_defs.push("MOBILE".into());
+        }
+        if self.analytic_aa {
+            shader_defs.push("ANALYTIC_AA".into());
+        }
+        shader_defs.push("MASK_PREPASS".into());
+
+        pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("rain_glare_mask_pipeline".into()),
+            layout: vec![self.compute_layout.clone()],
+            push_constant_ranges: vec![],
+            shader: self.shader.clone(),
+            shader_defs,
+            entry_point: "compute_mask".into(),
+        })
+    }
+
+    /// `requested_entry_point` if the effect's shader, composed with the current shader defs,
+    /// has such a `@fragment` function, else (with an error) the default.
+    fn resolve_entry_point(&self, shaders: &RainGlareShaders) -> RainGlareEntryPoint {
+        let default = RainGlareEntryPoint::default();
+        if self.requested_entry_point == default {
+            return default;
+        }
+        let Some(entry_points) =
+            fragment_entry_points(&shaders.0, self.shader.id(), &self.fragment_shader_defs())
+        else {
+            // Not loaded yet; checked again once it is.
+            return default;
+        };
+        if entry_points.contains(&self.requested_entry_point.0) {
+            return self.requested_entry_point.clone();
+        }
+        error!(
+            "rain glare: the shader has no @fragment function {:?} with the current shader \
+             defs; using {:?}",
+            self.requested_entry_point.0, default.0
+        );
+        default
+    }
+
+    fn fragment_shader_defs(&self) -> Vec<ShaderDefVal> {
+        let mut shader_defs = self.debug_view.shader_defs();
+        shader_defs.extend(self.coord_space.shader_defs());
+        shader_defs.extend(self.pattern.shader_defs());
+        shader_defs.extend(self.alpha_mode.shader_defs());
+        if self.mobile {
+            shader_defs.push("MOBILE".into());
+        }
+        if self.wetness {
+            shader_defs.push("WETNESS".into());
+        }
+        if self.compute {
+            shader_defs.push("COMPUTE_MASK".into());
+        }
+        if self.analytic_aa {
+            shader_defs.push("ANALYTIC_AA".into());
+        }
+        shader_defs
+    }
+
+    fn queue_pipeline(&mut self, pipeline_cache: &PipelineCache, format: TextureFormat) {
+        let shader_defs = self.fragment_shader_defs();
+        let mut targets = vec![Some(ColorTargetState {
+            format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        })];
+        if self.wetness {
+            targets.push(Some(ColorTargetState {
+                format: WETNESS_FORMAT,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            }));
+        }
+
+        let id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("rain_glare_pipeline".into()),
+            layout: vec![self.layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: self.entry_point.0.clone().into(),
+                targets,
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+        });
+        self.pipelines.insert(format, id);
+    }
+}
+
+impl FromWorld for RainGlarePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = render_device.create_bind_group_layout(
+            "rain_glare_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<RainGlareUniform>(true),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Unfilterable float accepts both the prepass depth and the fallback image.
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                ),
+            ),
+        );
+
+        let compute_layout = render_device.create_bind_group_layout(
+            "rain_glare_mask_bind_group_layout",
+            &BindGroupLayoutEntries::with_indices(
+                ShaderStages::COMPUTE,
+                (
+                    (2, uniform_buffer::<RainGlareUniform>(true)),
+                    (
+                        9,
+                        texture_storage_2d(MASK_FORMAT, StorageTextureAccess::WriteOnly),
+                    ),
+                ),
+            ),
+        );
+
+        let sampler_config = world.resource::<RainGlareSamplerConfig>();
+        let adapter = world.resource::<RenderAdapter>();
+        let sampler = render_device.create_sampler(&sampler_config.descriptor(adapter));
+
+        let mut pipeline = Self {
+            layout,
+            sampler,
+            shader: world.resource::<RainGlareShader>().0.clone(),
+            pipelines: HashMap::new(),
+            debug_view: *world.resource::<RainGlareDebugView>(),
+            coord_space: *world.resource::<RainGlareCoordSpace>(),
+            pattern: *world.resource::<RainGlarePattern>(),
+            alpha_mode: *world.resource::<RainGlareAlphaMode>(),
+            // Checked against the shader once it has been extracted.
+            entry_point: RainGlareEntryPoint::default(),
+            requested_entry_point: world.resource::<RainGlareEntryPoint>().clone(),
+            mobile: world.resource::<RainGlareQuality>().mobile,
+            wetness: world.resource::<RainGlareWetnessTargets>().enabled,
+            compute: world.resource::<RainGlareComputeEnabled>().0,
+            analytic_aa: world.resource::<RainGlareAnalyticAaEnabled>().0,
+            compute_layout,
+            compute_pipeline: None,
+        };
+        pipeline.queue_pipelines(world.resource::<PipelineCache>());
+        pipeline
+    }
+}
+
+/// The [pipeline options](RainGlarePipeline#pipeline-options) as extracted into the render
+/// world.
+#[derive(SystemParam)]
+struct RainGlarePipelineOptions<'w> {
+    debug_view: Res<'w, RainGlareDebugView>,
+    coord_space: Res<'w, RainGlareCoordSpace>,
+    pattern: Res<'w, RainGlarePattern>,
+    alpha_mode: Res<'w, RainGlareAlphaMode>,
+    entry_point: Res<'w, RainGlareEntryPoint>,
+    quality: Res<'w, RainGlareQuality>,
+    wetness: Res<'w, RainGlareWetnessTargets>,
+    compute: Res<'w, RainGlareComputeEnabled>,
+    analytic_aa: Res<'w, RainGlareAnalyticAaEnabled>,
+}
+
+/// Re-queues the pipelines when a global shader option changed since they were built.
+fn prepare_rain_glare_pipelines(
+    mut pipeline: ResMut<RainGlarePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    options: RainGlarePipelineOptions,
+    shaders: Res<RainGlareShaders>,
+    compute_supported: Res<RainGlareComputeSupported>,
+    views: Query<&RainGlareUniform>,
+) {
+    let RainGlarePipelineOptions {
+        debug_view,
+        coord_space,
+        pattern,
+        alpha_mode,
+        entry_point,
+        quality,
+        wetness,
+        compute,
+        analytic_aa,
+    } = options;
+    let unchanged = pipeline.debug_view == *debug_view
+        && pipeline.coord_space == *coord_space
+        && pipeline.pattern == *pattern
+        && pipeline.alpha_mode == *alpha_mode
+        && pipeline.requested_entry_point == *entry_point
+        && pipeline.mobile == quality.mobile
+        && pipeline.wetness == wetness.enabled
+        && pipeline.compute == compute.0
+        && pipeline.analytic_aa == analytic_aa.0;
+    // A custom entry point may only appear once the shader (or an import) has loaded.
+    let recheck = *entry_point != RainGlareEntryPoint::default() && shaders.is_changed();
+    if !unchanged || recheck {
+        pipeline.debug_view = *debug_view;
+        pipeline.coord_space = *coord_space;
+        pipeline.pattern = *pattern;
+        pipeline.alpha_mode = *alpha_mode;
+        pipeline.requested_entry_point = entry_point.clone();
+        pipeline.mobile = quality.mobile;
+        pipeline.wetness = wetness.enabled;
+        pipeline.compute = compute.0;
+        pipeline.analytic_aa = analytic_aa.0;
+
+        let resolved = pipeline.resolve_entry_point(&shaders);
+        if !unchanged || pipeline.entry_point != resolved {
+            pipeline.entry_point = resolved;
+            pipeline.queue_pipelines(&pipeline_cache);
+        }
+    }
+
+    // A `RainGlareVirtualResolution` mask needs the prepass even without `RainGlareCompute`.
+    let virtual_mask = views.iter().any(|view| view.virtual_size().is_some());
+    if compute_supported.0 && virtual_mask && pipeline.compute_pipeline.is_none() {
+        pipeline.compute_pipeline = Some(pipeline.queue_compute_pipeline(&pipeline_cache));
+    }
+}
+
+/// Queues a pipeline for any view format other than the two queued up front, so a camera
+/// whose main texture isn't the expected SDR/HDR format still gets the effect, unless its
+/// [`RainGlareFormatPolicy`] is strict.
+fn queue_view_format_pipelines(
+    mut pipeline: ResMut<RainGlarePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    views: Query<(Entity, &ViewTarget, Option<&RainGlareFormatPolicy>), With<RainGlareUniform>>,
+) {
+    let formats = views.iter().filter_map(|(entity, view_target, policy)| {
+        let format = view_target.main_texture_format();
+        if policy == Some(&RainGlareFormatPolicy::Strict)
+            && !RainGlarePipeline::expected_formats().contains(&format)
+        {
+            error_once!(
+                "rain glare: strict view {entity:?} uses unexpected main texture format \
+                 {format:?}; skipping the effect on it"
+            );
+            return None;
+        }
+        Some(format)
+    });
+
+    for format in unqueued_formats(&pipeline.pipelines, formats) {
+        warn_once!(
+            "rain glare: view uses unexpected main texture format {format:?} \
+             (HDR requested on hardware without the usual HDR format?); queueing a pipeline for it"
+        );
+        pipeline.queue_pipeline(&pipeline_cache, format);
+    }
+}
+
+/// The `formats` without an entry in `pipelines`, each once, so a format shared by several
+/// views (or seen again next frame) is queued only once.
+fn unqueued_formats<T>(
+    pipelines: &HashMap<TextureFormat, T>,
+    formats: impl IntoIterator<Item = TextureFormat>,
+) -> Vec<TextureFormat> {
+    let mut unqueued = Vec::new();
+    for format in formats {
+        if !pipelines.contains_key(&format) && !unqueued.contains(&format) {
+            unqueued.push(format);
+        }
+    }
+    unqueued
+}
+
+fn apply_env_quality(mut quality: ResMut<RainGlareQuality>) {
+    let Ok(value) = std::env::var(RainGlareQuality::ENV_VAR) else {
+        return;
+    };
+
+    match RainGlareQuality::from_preset(&value) {
+        Some(preset) => *quality = preset,
+        None => warn!(
+            "ignoring {}={value:?}; expected low, medium, high or mobile",
+            RainGlareQuality::ENV_VAR
+        ),
+    }
+}
+
+/// Raises degenerate `pattern_scale`s in the extracted settings before they are uploaded.
+fn clamp_pattern_scale(mut views: Query<&mut RainGlareUniform>) {
+    for mut settings in &mut views {
+        settings.pattern_scale = RainGlareSettings::sanitized_pattern_scale(settings.pattern_scale);
+    }
+}
+
+/// Applies [`RainGlareMotionBlurAware`] to the extracted settings before they are uploaded.
+fn compensate_motion_blur(
+    graph: Res<RenderGraph>,
+    mut blur_in_graph: Local<Option<bool>>,
+    mut views: Query<
+        (
+            &mut RainGlareUniform,
+            &MotionBlur,
+            Option<&ViewPrepassTextures>,
+        ),
+        With<RainGlareMotionBlurAware>,
+    >,
+) {
+    // The graph is fixed once the app runs, so look the node up once.
+    let blur_in_graph = *blur_in_graph.get_or_insert_with(|| {
+        graph
+            .get_sub_graph(Core3d)
+            .is_some_and(|core_3d| core_3d.get_node_state(Node3d::MotionBlur).is_ok())
+    });
+    if !blur_in_graph {
+        return;
+    }
+
+    for (mut settings, blur, prepass_textures) in &mut views {
+        let has_motion_vectors =
+            prepass_textures.is_some_and(|textures| textures.motion_vectors.is_some());
+        if blur.samples == 0 || blur.shutter_angle <= 0.0 || !has_motion_vectors {
+            continue;
+        }
+
+        settings.motion_response = 0.0;
+        settings.intensity *= 1.0 - 0.5 * blur.shutter_angle.min(1.0);
+    }
+}
+
+/// Applies [`RainGlareLimits`] to the extracted settings before they are uploaded.
+fn apply_rain_limits(limits: Res<RainGlareLimits>, mut views: Query<&mut RainGlareUniform>) {
+    for mut settings in &mut views {
+        settings.intensity = settings.intensity.min(limits.max_intensity);
+        if limits.clamp_thickness {
+            settings.mask_thickness_px = RainGlareSettings::clamped_mask_thickness(
+                settings.mask_thickness_px,
+                settings.streak_length_px,
+            );
+        }
+    }
+}
+
+/// Applies [`RainGlareQuality`] to the extracted settings before they are uploaded.
+fn clamp_rain_density(quality: Res<RainGlareQuality>, mut views: Query<&mut RainGlareUniform>) {
+    for mut settings in &mut views {
+        // The shader multiplies density by `rain_amount`, so cap the product.
+        let max_density = quality.max_density / settings.rain_amount.max(1e-6);
+        settings.rain_density = settings.rain_density.min(max_density);
+    }
+}
+
+fn prepare_rain_glare_output_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &ViewTarget), With<RainGlareCaptureOutput>>,
+) {
+    for (entity, view_target) in &views {
+        let texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("rain_glare_output_texture"),
+                size: view_target.main_texture().size(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: view_target.main_texture_format(),
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(RainGlareOutputTexture { texture });
+    }
+}
+
+/// Side length of the grid of pixels read back per view for [`RainGlareAutoThreshold`] and
+/// [`RainGlareCoverage`].
+const READBACK_GRID: u32 = 16;
+
+const READBACK_IDLE: u8 = 0;
+const READBACK_COPIED: u8 = 1;
+const READBACK_MAPPING: u8 = 2;
+const READBACK_MAPPED: u8 = 3;
+
+/// Render-world readback buffers for [`RainGlareAutoThreshold`] and [`RainGlareCoverage`],
+/// one per view.
+#[derive(Resource, Default)]
+struct RainGlareReadbacks {
+    enabled: bool,
+    /// Queued the first time readbacks are enabled.
+    pipeline: Option<(BindGroupLayout, CachedRenderPipelineId)>,
+    views: HashMap<Entity, ViewReadback>,
+}
+
+/// Format of the target the grid is gathered into before the copy.
+const READBACK_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Bytes per row of the readback buffer: one row of the grid, padded for the copy.
+const READBACK_ROW_BYTES: u32 =
+    (READBACK_GRID * 8).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+/// A `READBACK_GRID` x `2 * READBACK_GRID` target holding the grid, first for the scene
+/// before the pass (top half), then for its output, and a mappable buffer it is copied into.
+/// `state` moves idle -> copied (node) -> mapping (cleanup) -> mapped (map callback) -> idle
+/// (prepare, after reading it), so only one readback per view is ever in flight.
+struct ViewReadback {
+    texture: CachedTexture,
+    buffer: Buffer,
+    state: Arc<AtomicU8>,
+}
+
+impl ViewReadback {
+    fn new(render_device: &RenderDevice) -> Self {
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("rain_glare_readback_texture"),
+            size: Extent3d {
+                width: READBACK_GRID,
+                height: 2 * READBACK_GRID,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: READBACK_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("rain_glare_readback_buffer"),
+            size: u64::from(2 * READBACK_GRID * READBACK_ROW_BYTES),
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            texture: CachedTexture {
+                default_view: texture.create_view(&TextureViewDescriptor::default()),
+                texture,
+            },
+            buffer,
+            state: Arc::new(AtomicU8::new(READBACK_IDLE)),
+        }
+    }
+
+    /// Whether a new readback can start this frame.
+    fn ready(&self) -> bool {
+        self.state.load(Ordering::Acquire) == READBACK_IDLE
+    }
+
+    /// Gathers the grid of `source` into half `half` (0 = scene, 1 = output) of the target.
+    fn gather_grid(
+        &self,
+        render_context: &mut RenderContext,
+        layout: &BindGroupLayout,
+        pipeline: &RenderPipeline,
+        source: &TextureView,
+        half: u32,
+    ) {
+        let bind_group = render_context.render_device().create_bind_group(
+            "rain_glare_readback_bind_group",
+            layout,
+            &BindGroupEntries::single(source),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("rain_glare_readback_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &self.texture.default_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        let grid = READBACK_GRID as f32;
+        render_pass.set_viewport(0.0, half as f32 * grid, grid, grid, 0.0, 1.0);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Copies both halves of the target into the buffer.
+    fn copy_grid(&self, encoder: &mut CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            self.texture.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(READBACK_ROW_BYTES),
+                    rows_per_image: None,
+                },
+            },
+            self.texture.texture.size(),
+        );
+    }
+
+    /// Decodes the mapped buffer into linear colors.
+    fn read_frame(&self) -> ReadbackFrame {
+        let data = self.buffer.slice(..).get_mapped_range();
+        let frame = decode_readback(&data);
+        drop(data);
+        self.buffer.unmap();
+        frame
+    }
+}
+
+/// Splits `READBACK_ROW_BYTES`-strided rows of `Rgba16Float` texels into the scene and
+/// output grids.
+fn decode_readback(data: &[u8]) -> ReadbackFrame {
+    let row_len = (READBACK_GRID * 8) as usize;
+    let mut scene: Vec<Vec3> = data
+        .chunks_exact(READBACK_ROW_BYTES as usize)
+        .flat_map(|row| row[..row_len].chunks_exact(8))
+        .map(|texel| {
+            let channel = |i: usize| f16::from_le_bytes([texel[i], texel[i + 1]]).to_f32();
+            Vec3::new(channel(0), channel(2), channel(4))
+        })
+        .collect();
+    let output = scene.split_off(scene.len() / 2);
+    ReadbackFrame { scene, output }
+}
+
+fn extract_readback_flags(
+    auto_threshold: Extract<Option<Res<RainGlareAutoThreshold>>>,
+    coverage: Extract<Option<Res<RainGlareCoverage>>>,
+    mut readbacks: ResMut<RainGlareReadbacks>,
+) {
+    readbacks.enabled = auto_threshold.is_some() || coverage.is_some();
+}
+
+/// Format of the [`RainGlareWetness`] buffers.
+const WETNESS_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+/// Render-world wetness buffers for [`RainGlareWetness`], one pair per view.
+#[derive(Resource, Default)]
+struct RainGlareWetnessTargets {
+    enabled: bool,
+    views: HashMap<Entity, WetnessTarget>,
+}
+
+/// Two wetness textures the pass alternates between: it reads last frame's wetness from
+/// `views[read]` and writes this frame's into the other one.
+struct WetnessTarget {
+    size: Extent3d,
+    views: [TextureView; 2],
+    read: usize,
+}
+
+impl WetnessTarget {
+    fn new(render_device: &RenderDevice, size: Extent3d) -> Self {
+        let view = || {
+            render_device
+                .create_texture(&TextureDescriptor {
+                    label: Some("rain_glare_wetness_texture"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: WETNESS_FORMAT,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        Self {
+            size,
+            views: [view(), view()],
+            read: 0,
+        }
+    }
+
+    fn read_view(&self) -> &TextureView {
+        &self.views[self.read]
+    }
+
+    fn write_view(&self) -> &TextureView {
+        &self.views[1 - self.read]
+    }
+}
+
+/// Render-world switch for [`RainGlareAnalyticAa`].
+#[derive(Resource, Default)]
+struct RainGlareAnalyticAaEnabled(bool);
+
+fn extract_analytic_aa_flag(
+    analytic_aa: Extract<Option<Res<RainGlareAnalyticAa>>>,
+    mut enabled: ResMut<RainGlareAnalyticAaEnabled>,
+) {
+    enabled.0 = analytic_aa.is_some();
+}
+
+/// Format of the [`RainGlareCompute`] mask.
+const MASK_FORMAT: TextureFormat = TextureFormat::R32Float;
+
+/// Side length of the mask prepass's workgroups; matches `@workgroup_size` in the shader.
+const MASK_WORKGROUP_SIZE: u32 = 8;
+
+/// Render-world switch for [`RainGlareCompute`]: requested and supported by the device.
+#[derive(Resource, Default)]
+struct RainGlareComputeEnabled(bool);
+
+/// Whether the device can run the mask prepass at all, for [`RainGlareVirtualResolution`].
+#[derive(Resource, Default)]
+struct RainGlareComputeSupported(bool);
+
+/// A view's streak mask for the current frame, written by the compute prepass.
+#[derive(Component)]
+struct RainGlareMaskTexture(CachedTexture);
+
+fn extract_compute_flag(
+    compute: Extract<Option<Res<RainGlareCompute>>>,
+    render_device: Res<RenderDevice>,
+    mut enabled: ResMut<RainGlareComputeEnabled>,
+    mut compute_supported: ResMut<RainGlareComputeSupported>,
+) {
+    // Downlevel devices without compute shaders (WebGL2) report zero for both limits.
+    let limits = render_device.limits();
+    let supported = limits.max_compute_workgroups_per_dimension > 0
+        && limits.max_storage_textures_per_shader_stage > 0;
+    if compute.is_some() && !supported {
+        warn_once!("rain glare: RainGlareCompute needs compute shaders; using the fragment pass");
+    }
+    enabled.0 = compute.is_some() && supported;
+    compute_supported.0 = supported;
+}
+
+/// Size of a view's prepass mask: the [`RainGlareVirtualResolution`] if set, else the
+/// view's `native` size with [`RainGlareCompute`] on, else `None` (no prepass).
+fn mask_size(uniform: &RainGlareUniform, native: Extent3d, compute: bool) -> Option<Extent3d> {
+    match uniform.virtual_size() {
+        Some(size) => Some(Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        }),
+        None => compute.then_some(native),
+    }
+}
+
+fn prepare_rain_mask_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    enabled: Res<RainGlareComputeEnabled>,
+    supported: Res<RainGlareComputeSupported>,
+    views: Query<(Entity, &ViewTarget, &RainGlareUniform)>,
+) {
+    if !supported.0 {
+        return;
+    }
+
+    for (entity, view_target, uniform) in &views {
+        let Some(size) = mask_size(uniform, view_target.main_texture().size(), enabled.0) else {
+            continue;
+        };
+        let texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("rain_glare_mask_texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: MASK_FORMAT,
+                usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(RainGlareMaskTexture(texture));
+    }
+}
+
+fn extract_wetness_flag(
+    wetness: Extract<Option<Res<RainGlareWetness>>>,
+    mut targets: ResMut<RainGlareWetnessTargets>,
+) {
+    targets.enabled = wetness.is_some();
+}
+
+/// Flips each view's wetness buffers, (re)creating them for new or resized views. New
+/// textures start zeroed, i.e. dry.
+fn prepare_rain_wetness(
+    render_device: Res<RenderDevice>,
+    mut targets: ResMut<RainGlareWetnessTargets>,
+    views: Query<(Entity, &ViewTarget), With<RainGlareUniform>>,
+) {
+    if !targets.enabled {
+        targets.views.clear();
+        return;
+    }
+    targets.views.retain(|entity, _| views.contains(*entity));
+
+    for (entity, view_target) in &views {
+        let size = view_target.main_texture().size();
+        match targets.views.get_mut(&entity) {
+            Some(target) if target.size == size => target.read = 1 - target.read,
+            _ => {
+                targets
+                    .views
+                    .insert(entity, WetnessTarget::new(&render_device, size));
+            }
+        }
+    }
+}
+
+/// Creates readback targets for new views, queues the pipeline gathering into them and hands
+/// finished readbacks to the main world.
+fn prepare_rain_readbacks(
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    samples: Res<RainGlareReadbackSamples>,
+    mut readbacks: ResMut<RainGlareReadbacks>,
+    views: Query<Entity, (With<ViewTarget>, With<RainGlareUniform>)>,
+) {
+    if !readbacks.enabled {
+        readbacks.views.clear();
+        return;
+    }
+    if readbacks.pipeline.is_none() {
+        readbacks.pipeline = Some(queue_readback_pipeline(&render_device, &pipeline_cache));
+    }
+    readbacks.views.retain(|entity, _| views.contains(*entity));
+
+    for entity in &views {
+        if let Some(readback) = readbacks.views.get(&entity) {
+            if readback.state.load(Ordering::Acquire) == READBACK_MAPPED {
+                let frame = readback.read_frame();
+                samples.0.lock().unwrap().insert(entity, frame);
+                readback.state.store(READBACK_IDLE, Ordering::Release);
+            }
+            continue;
+        }
+        readbacks
+            .views
+            .insert(entity, ViewReadback::new(&render_device));
+    }
+}
+
+fn queue_readback_pipeline(
+    render_device: &RenderDevice,
+    pipeline_cache: &PipelineCache,
+) -> (BindGroupLayout, CachedRenderPipelineId) {
+    let layout = render_device.create_bind_group_layout(
+        "rain_glare_readback_bind_group_layout",
+        &BindGroupLayoutEntries::single(
+            ShaderStages::FRAGMENT,
+            // Only loaded, so any float view format will do.
+            texture_2d(TextureSampleType::Float { filterable: false }),
+        ),
+    );
+    let id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+        label: Some("rain_glare_readback_pipeline".into()),
+        layout: vec![layout.clone()],
+        vertex: fullscreen_shader_vertex_state(),
+        fragment: Some(FragmentState {
+            shader: RAIN_GLARE_READBACK_SHADER_HANDLE,
+            shader_defs: vec![ShaderDefVal::UInt("READBACK_GRID".into(), READBACK_GRID)],
+            entry_point: "fragment".into(),
+            targets: vec![Some(ColorTargetState {
+                format: READBACK_FORMAT,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        push_constant_ranges: vec![],
+    });
+    (layout, id)
+}
+
+/// Starts mapping the buffers copied into this frame, once the graph has been submitted.
+fn map_rain_readbacks(render_device: Res<RenderDevice>, readbacks: Res<RainGlareReadbacks>) {
+    let mut mapping = false;
+    for readback in readbacks.views.values() {
+        if readback
+            .state
+            .compare_exchange(
+                READBACK_COPIED,
+                READBACK_MAPPING,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_err()
+        {
+            continue;
+        }
+
+        let state = readback.state.clone();
+        render_device.map_buffer(&readback.buffer.slice(..), MapMode::Read, move |result| {
+            let next = if result.is_ok() {
+                READBACK_MAPPED
+            } else {
+                READBACK_IDLE
+            };
+            state.store(next, Ordering::Release);
+        });
+        mapping = true;
+    }
+
+    if mapping {
+        render_device.poll(Maintain::Poll);
+    }
+}
+
+/* fn advance_rain_time(time: Res<Time>, mut query: Query<&mut RainGlareSettings>) {
+    for mut settings in &mut query {
+        settings.time += time.delta_seconds();
+    }
+} */
+/// Gives every camera with [`RainGlareSettings`] its [`RainGlareDerived`] values.
+fn add_rain_glare_derived(
+    mut commands: Commands,
+    q: Query<Entity, (With<RainGlareSettings>, Without<RainGlareDerived>)>,
+) {
+    for entity in &q {
+        commands.entity(entity).insert(RainGlareDerived::default());
+    }
+}
+
+/// Everything `advance_rain_time` reads or writes on a rain camera.
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct RainCamera {
+    camera: &'static Camera,
+    transform: Ref<'static, Transform>,
+    global_transform: Ref<'static, GlobalTransform>,
+    projection: Option<&'static Projection>,
+    settings: &'static mut RainGlareSettings,
+    derived: Option<&'static mut RainGlareDerived>,
+    time_source: Option<&'static RainGlareTimeSource>,
+    frozen: Has<RainGlareFreeze>,
+    ignore_roll: Has<RainGlareIgnoreRoll>,
+}
+
+/// Shared clock used by `advance_rain_time` while [`RainGlareSmoothing`] is active.
+#[derive(Default)]
+struct SmoothedRainClock {
+    time: f32,
+    delta: Option<f32>,
+}
+
+impl SmoothedRainClock {
+    fn tick(&mut self, time: &Time, smoothing: f32) -> f32 {
+        if smoothing <= 0.0 {
+            // Stay in sync with the engine clock so enabling smoothing doesn't jump.
+            self.time = time.elapsed_seconds();
+            self.delta = None;
+            return self.time;
+        }
+
+        let dt = time.delta_seconds();
+        let strength = smoothing.clamp(0.0, 0.99);
+        let delta = self
+            .delta
+            .map_or(dt, |prev| prev + (dt - prev) * (1.0 - strength));
+        self.delta = Some(delta);
+        self.time += delta;
+        self.time
+    }
+}
+
+/// Clock held between steps by `advance_rain_time` while [`RainGlareUpdateHz`] is set.
+#[derive(Default)]
+struct SteppedRainClock {
+    held: Option<f32>,
+}
+
+impl SteppedRainClock {
+    fn step(&mut self, t: f32, hz: f32) -> f32 {
+        if !hz.is_finite() || hz <= 0.0 {
+            self.held = None;
+            return t;
+        }
+
+        let period = hz.recip();
+        // Start from the live clock, and resync if it went backwards (e.g. a new epoch).
+        let held = self.held.filter(|&held| held <= t).unwrap_or(t);
+        // Advance by whole periods only; the remainder accumulates towards the next step.
+        let held = held + ((t - held) / period).floor() * period;
+        self.held = Some(held);
+        held
+    }
+}
+
+fn advance_rain_time(
+    time: Res<Time>,
+    smoothing: Res<RainGlareSmoothing>,
+    update_hz: Res<RainGlareUpdateHz>,
+    network_time: Option<Res<RainGlareNetworkTime>>,
+    mut clocks: Local<(SmoothedRainClock, SteppedRainClock)>,
+    mut q: Query<RainCamera, With<Camera3d>>,
+    ticks: SystemChangeTick,
+) {
+    let (clock, stepped) = &mut *clocks;
+    // The local clock keeps ticking underneath, ready for when the network time is removed.
+    let local = clock.tick(&time, smoothing.0);
+    let t = network_time.map_or(local, |network| network.epoch_seconds());
+    let t = stepped.step(t, update_hz.0);
+
+    for RainCameraItem {
+        camera,
+        transform,
+        global_transform,
+        projection,
+        mut settings,
+        derived,
+        time_source,
+        frozen,
+        ignore_roll,
+    } in &mut q
+    {
+        // Inactive cameras don't render; they pick the clock back up when re-enabled.
+        if !camera.is_active {
+            continue;
+        }
+
+        if !frozen {
+            settings.time = match time_source {
+                None => t,
+                Some(RainGlareTimeSource::Manual(manual)) => *manual,
+                Some(RainGlareTimeSource::Scaled(scale)) => t * scale,
+            };
+        }
+
+        // A camera spawned since the last propagation still has the GlobalTransform it was
+        // spawned with (usually the identity, looking down -Z at the horizon), whatever its
+        // Transform or parents say. Keep the last camera-derived values, the horizon-facing
+        // defaults on a fresh camera, until propagation has caught up.
+        if transform_pending(&transform, &global_transform, ticks.this_run()) {
+            continue;
+        }
+
+        // World-space view direction (forward).
+        // GlobalTransform::forward() returns Dir3; convert to Vec3.
+        let forward: Vec3 = global_transform.forward().into();
+
+        // World up (assuming Y-up). Change if you use a different up-axis.
+        let world_up = Vec3::Y;
+
+        // Sharpen so it’s strong near the horizon, fades faster near zenith/nadir.
+        let exponent = 2.0;
+
+        settings.view_angle_factor = compute_view_angle_factor(forward, world_up, exponent);
+
+        // Added in `PreUpdate`, so only missing on a camera spawned this frame.
+        let Some(mut derived) = derived else {
+            continue;
+        };
+
+        derived.zoom_factor = match projection {
+            Some(Projection::Perspective(perspective)) => compute_zoom_factor(perspective.fov),
+            _ => 1.0,
+        };
+
+        derived.camera_right = global_transform.right().into();
+        derived.camera_up = global_transform.up().into();
+
+        derived.gravity_screen_dir = if ignore_roll {
+            Vec2::Y
+        } else {
+            compute_gravity_screen_dir(&global_transform, world_up)
+        };
+    }
+}
+
+/// Whether `global` was added since the system last ran and hasn't been propagated yet:
+/// propagation always writes a newly added `GlobalTransform`, after `local` was set.
+fn transform_pending(
+    local: &Ref<Transform>,
+    global: &Ref<GlobalTransform>,
+    this_run: Tick,
+) -> bool {
+    global.is_added()
+        && !global
+            .last_changed()
+            .is_newer_than(local.last_changed(), this_run)
+}
+
+/// Screen-space direction (UV orientation, +y down) of `-up` as seen by a camera.
+///
+/// (0, 1) for an unrolled camera; rotates with roll. Falls back to (0, 1) when looking
+/// straight up/down, where the projection degenerates (and the effect is faded out by
+/// `view_angle_factor` anyway).
+pub fn compute_gravity_screen_dir(camera: &GlobalTransform, up: Vec3) -> Vec2 {
+    let down = -up;
+    let right: Vec3 = camera.right().into();
+    let camera_up: Vec3 = camera.up().into();
+
+    Vec2::new(down.dot(right), -down.dot(camera_up))
+        .try_normalize()
+        .unwrap_or(Vec2::Y)
+}
+
+/// Zoom of a perspective camera with vertical field of view `fov` (radians) relative to
+/// Bevy's default FOV, as written into [`RainGlareDerived::zoom_factor`]: 1.0 at the
+/// default, 2.0 when the view covers half the height.
+pub fn compute_zoom_factor(fov: f32) -> f32 {
+    let base = PerspectiveProjection::default().fov;
+    (base * 0.5).tan() / (fov * 0.5).tan().max(1e-6)
+}
+
+/// How “horizon-facing” a view direction is, as written into
+/// [`RainGlareSettings::view_angle_factor`].
+///
+/// Returns ~1.0 when `forward` is perpendicular to `up` (looking at the horizon) and ~0.0
+/// when it is parallel (straight up/down); `exponent` sharpens the falloff in between.
+/// Both vectors are expected to be normalized.
+pub fn compute_view_angle_factor(forward: Vec3, up: Vec3, exponent: f32) -> f32 {
+    // How much the view is pointing up/down.
+    let vertical = forward.dot(up); // -1..1
+    let horizon = (1.0 - vertical.abs()).clamp(0.0, 1.0);
+
+    horizon.powf(exponent)
+}
+
+/// One-shot system that applies `patch` to every camera's [`RainGlareSettings`], e.g.
+/// `world.run_system_once(set_rain_settings(patch))` from a dev console.
+pub fn set_rain_settings(
+    patch: RainGlareSettingsPatch,
+) -> impl FnMut(Query<&mut RainGlareSettings>) {
+    move |mut q| {
+        for mut settings in &mut q {
+            settings.apply_patch(patch);
+        }
+    }
+}
+
+/// One-shot system setting `intensity` on every rain camera; see [`set_rain_settings`].
+pub fn set_rain_intensity(intensity: f32) -> impl FnMut(Query<&mut RainGlareSettings>) {
+    set_rain_settings(RainGlareSettingsPatch {
+        intensity: Some(intensity),
+        ..default()
+    })
+}
+
+/// One-shot system setting `rain_density` on every rain camera; see [`set_rain_settings`].
+pub fn set_rain_density(rain_density: f32) -> impl FnMut(Query<&mut RainGlareSettings>) {
+    set_rain_settings(RainGlareSettingsPatch {
+        rain_density: Some(rain_density),
+        ..default()
+    })
+}
+
+/// One-shot system setting `wind` on every rain camera; see [`set_rain_settings`].
+pub fn set_rain_wind(wind: Vec2) -> impl FnMut(Query<&mut RainGlareSettings>) {
+    set_rain_settings(RainGlareSettingsPatch {
+        wind: Some(wind),
+        ..default()
+    })
+}
+
+/// One-shot system setting `speed` on every rain camera; see [`set_rain_settings`].
+pub fn set_rain_speed(speed: f32) -> impl FnMut(Query<&mut RainGlareSettings>) {
+    set_rain_settings(RainGlareSettingsPatch {
+        speed: Some(speed),
+        ..default()
+    })
+}
+
+fn ramp_rain_density(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut RainGlareRamp, &mut RainGlareSettings)>,
+) {
+    for (entity, mut ramp, mut settings) in &mut q {
+        ramp.elapsed += time.delta_seconds();
+
+        let t = if ramp.duration > 0.0 {
+            (ramp.elapsed / ramp.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        settings.rain_density = ramp.target_density * t;
+
+        if t >= 1.0 {
+            commands.entity(entity).remove::<RainGlareRamp>();
+        }
+    }
+}
+
+fn transition_rain_settings(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut finished: EventWriter<RainGlareTransitionFinished>,
+    mut q: Query<(Entity, &mut RainGlareTransition, &mut RainGlareSettings)>,
+) {
+    for (entity, mut transition, mut settings) in &mut q {
+        let from = *transition.from.get_or_insert(*settings);
+        transition.elapsed += time.delta_seconds();
+
+        let t = if transition.duration > 0.0 {
+            (transition.elapsed / transition.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let eased = t * t * (3.0 - 2.0 * t);
+
+        *settings = from
+            .lerp(&transition.target, eased)
+            .with_live_fields_of(&settings);
+
+        if t >= 1.0 {
+            commands.entity(entity).remove::<RainGlareTransition>();
+            if transition.notify {
+                finished.send(RainGlareTransitionFinished { entity });
+            }
+        }
+    }
+}
+
+fn select_rain_lod(
+    transforms: Query<&GlobalTransform>,
+    mut q: Query<(&GlobalTransform, &mut RainGlareLod, &mut RainGlareSettings)>,
+) {
+    for (camera, mut lod, mut settings) in &mut q {
+        let reference = match lod.reference {
+            RainGlareLodReference::Point(point) => point,
+            RainGlareLodReference::Entity(entity) => match transforms.get(entity) {
+                Ok(transform) => transform.translation(),
+                Err(_) => continue,
+            },
+        };
+        let distance = camera.translation().distance(reference);
+        let level = lod.levels.iter().rposition(|(d, _)| distance >= *d);
+
+        // Keep tracking edits made while the camera's own settings are active.
+        if lod.active.is_none() {
+            lod.near = Some(*settings);
+        }
+        if level == lod.active {
+            continue;
+        }
+
+        let authored = match level {
+            Some(index) => lod.levels[index].1,
+            None => lod.near.unwrap_or(*settings),
+        };
+        *settings = authored.with_live_fields_of(&settings);
+        lod.active = level;
+    }
+}
+
+fn fade_out_rain(time: Res<Time>, mut q: Query<(&mut RainGlareFadeOut, &mut RainGlareSettings)>) {
+    const EPSILON: f32 = 1e-4;
+
+    for (mut fade, mut settings) in &mut q {
+        let density = settings.rain_density;
+
+        match fade.elapsed {
+            None if density > EPSILON => {
+                fade.last_density = density;
+                fade.last_intensity = settings.intensity;
+            }
+            None if fade.last_density > EPSILON => fade.elapsed = Some(0.0),
+            None => continue,
+            Some(_) if density > EPSILON && density != fade.last_density => {
+                // Rain resumed mid-fade.
+                settings.intensity = fade.last_intensity;
+                fade.last_density = density;
+                fade.elapsed = None;
+                continue;
+            }
+            Some(elapsed) => fade.elapsed = Some(elapsed + time.delta_seconds()),
+        }
+
+        let Some(elapsed) = fade.elapsed else {
+            continue;
+        };
+
+        let t = if fade.duration > 0.0 {
+            (elapsed / fade.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        if t >= 1.0 {
+            settings.rain_density = 0.0;
+            settings.intensity = fade.last_intensity;
+            fade.last_density = 0.0;
+            fade.elapsed = None;
+        } else {
+            settings.rain_density = fade.last_density;
+            settings.intensity = fade.last_intensity * (1.0 - t);
+        }
+    }
+}
+
+fn fade_indoors(time: Res<Time>, mut q: Query<(Option<&RainGlareIndoors>, &mut RainGlareDerived)>) {
+    for (indoors, mut derived) in &mut q {
+        let (target, crossfade) = match indoors {
+            Some(indoors) => (if indoors.indoors { 0.0 } else { 1.0 }, indoors.crossfade),
+            None => (1.0, 0.0),
+        };
+
+        let current = derived.outdoor_amount;
+        if current == target {
+            continue;
+        }
+
+        derived.outdoor_amount = if crossfade > 0.0 {
+            let step = time.delta_seconds() / crossfade;
+            current + (target - current).clamp(-step, step)
+        } else {
+            target
+        };
+    }
+}
+
+fn relax_rain_wind(
+    time: Res<Time>,
+    mut relaxed: Local<HashMap<Entity, Vec2>>,
+    mut q: Query<(Entity, &RainGlareWindRelax, &mut RainGlareSettings)>,
+) {
+    let dt = time.delta_seconds();
+    let mut written = HashMap::new();
+    for (entity, relax, settings) in &mut q {
+        let wind = settings.wind;
+        // Anything other than the wind this system left means a gust is being applied.
+        let gusting = relaxed.get(&entity) != Some(&wind);
+        let target = if gusting || relax.rate <= 0.0 {
+            wind
+        } else {
+            let eased = relax.base + (wind - relax.base) * (-relax.rate * dt).exp();
+            // Settle exactly on the base rather than creeping towards it forever.
+            if eased.distance_squared(relax.base) < 1e-8 {
+                relax.base
+            } else {
+                eased
+            }
+        };
+
+        settings
+            .map_unchanged(|settings| &mut settings.wind)
+            .set_if_neq(target);
+        written.insert(entity, target);
+    }
+    *relaxed = written;
+}
+
+fn apply_camera_viewport(mut q: Query<(&Camera, &mut RainGlareDerived)>) {
+    for (camera, mut derived) in &mut q {
+        derived.viewport = viewport_rect(camera.viewport.as_ref(), camera.physical_target_size());
+    }
+}
+
+/// `viewport` as a fraction of a `target_size` target (origin in `xy`, size in `zw`), or the
+/// whole target when either is unknown.
+fn viewport_rect(viewport: Option<&Viewport>, target_size: Option<UVec2>) -> Vec4 {
+    let (Some(viewport), Some(target_size)) = (viewport, target_size) else {
+        return RainGlareDerived::DEFAULT.viewport;
+    };
+    if target_size.cmpeq(UVec2::ZERO).any() {
+        return RainGlareDerived::DEFAULT.viewport;
+    }
+    let target = target_size.as_vec2();
+    let min = viewport.physical_position.min(target_size).as_vec2() / target;
+    let max = (viewport.physical_position + viewport.physical_size)
+        .min(target_size)
+        .as_vec2()
+        / target;
+    Vec4::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+fn apply_virtual_resolution(
+    mut q: Query<(Option<&RainGlareVirtualResolution>, &mut RainGlareDerived)>,
+) {
+    for (resolution, mut derived) in &mut q {
+        derived.virtual_resolution =
+            resolution.map_or(Vec2::ZERO, |resolution| resolution.0.as_vec2());
+    }
+}
+
+/// Warns once if [`RainGlareProfiling`] is on but the device can't time the pass on the GPU.
+fn check_profiling_support(
+    profiling: Extract<Res<RainGlareProfiling>>,
+    render_device: Res<RenderDevice>,
+) {
+    let timestamps = WgpuFeatures::TIMESTAMP_QUERY | WgpuFeatures::TIMESTAMP_QUERY_INSIDE_PASSES;
+    if profiling.enabled && !render_device.features().contains(timestamps) {
+        warn_once!(
+            "rain glare: the device lacks {timestamps:?}, so RainGlareProfiling only reports \
+             CPU time"
+        );
+    }
+}
+
+fn read_rain_glare_profiling(
+    store: Option<Res<DiagnosticsStore>>,
+    mut profiling: ResMut<RainGlareProfiling>,
+) {
+    if !profiling.enabled {
+        return;
+    }
+
+    let latest = |path: &DiagnosticPath| {
+        store
+            .as_ref()
+            .and_then(|store| store.get(path))
+            .and_then(|diagnostic| diagnostic.value())
+    };
+    let gpu_time_ms = latest(&RainGlareProfiling::GPU_TIME);
+    let cpu_time_ms = latest(&RainGlareProfiling::CPU_TIME);
+
+    // Only touch the resource when something changed, so it isn't re-extracted every frame.
+    if profiling.gpu_time_ms != gpu_time_ms || profiling.cpu_time_ms != cpu_time_ms {
+        profiling.gpu_time_ms = gpu_time_ms;
+        profiling.cpu_time_ms = cpu_time_ms;
+    }
+}
+
+/// Writes a [`RainGlareDriver`]'s amount into every camera's
+/// [`RainGlareDerived::rain_amount`]. Does nothing while the driver resource is absent.
+pub fn drive_rain_amount<D: RainGlareDriver>(
+    driver: Option<Res<D>>,
+    mut q: Query<&mut RainGlareDerived>,
+) {
+    let Some(driver) = driver else {
+        return;
+    };
+    let amount = driver.rain_amount().max(0.0);
+
+    for mut derived in &mut q {
+        derived.rain_amount = amount;
+    }
+}
+
+fn drive_rain_beat(beat: Option<Res<RainGlareBeat>>, mut q: Query<&mut RainGlareDerived>) {
+    let beat = beat.map_or(0.0, |beat| beat.0.clamp(0.0, 1.0));
+
+    for mut derived in &mut q {
+        derived.beat = beat;
+    }
+}
+
+fn warn_sky_only_without_depth(msaa: Res<Msaa>, q: Query<(&RainGlareSettings, Has<DepthPrepass>)>) {
+    for (settings, has_depth) in &q {
+        if settings.sky_only <= 0.0 {
+            continue;
+        }
+
+        if !has_depth {
+            warn_once!(
+                "RainGlareSettings::sky_only needs a DepthPrepass on the camera; ignoring it"
+            );
+        } else if *msaa != Msaa::Off {
+            warn_once!("RainGlareSettings::sky_only needs Msaa::Off; ignoring it");
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+fn warn_blob_streaks(q: Query<(Entity, &RainGlareSettings)>) {
+    for (entity, settings) in &q {
+        if settings.has_blob_streaks() {
+            warn_once!(
+                "rain glare: mask_thickness_px {} is over {} x streak_length_px {} on {entity:?}; \
+                 the streaks will look like blobs",
+                settings.mask_thickness_px,
+                RainGlareSettings::MAX_THICKNESS_RATIO,
+                settings.streak_length_px
+            );
+        }
+    }
+}
+
+fn compensate_rain_exposure(
+    compensation: Res<RainGlareExposureCompensation>,
+    ambient: Option<Res<AmbientLight>>,
+    mut q: Query<&mut RainGlareDerived>,
+) {
+    let scale = match (compensation.0, ambient) {
+        (true, Some(ambient)) => RainGlareExposureCompensation::scale_for(ambient.brightness),
+        _ => 1.0,
+    };
+
+    for mut derived in &mut q {
+        derived.exposure_compensation = scale;
+    }
+}
+
+fn apply_time_of_day(
+    time_of_day: Option<Res<RainGlareTimeOfDay>>,
+    curve: Res<RainGlareTimeOfDayCurve>,
+    mut q: Query<&mut RainGlareDerived>,
+) {
+    let daylight = match time_of_day {
+        Some(time_of_day) => (curve.0)(time_of_day.0.rem_euclid(1.0)),
+        None => RainGlareDaylight {
+            tint: Srgba::WHITE,
+            intensity: 1.0,
+        },
+    };
+    let tint = daylight.linear_tint();
+
+    for mut derived in &mut q {
+        derived.daylight_tint = tint;
+        derived.daylight_intensity = daylight.intensity;
+    }
+}
+
+fn request_letterbox_usage(
+    mut q: Query<&mut CameraMainTextureUsages, With<RainGlareLetterboxAware>>,
+) {
+    let copy = TextureUsages::COPY_SRC | TextureUsages::COPY_DST;
+    for mut usages in &mut q {
+        if !usages.0.contains(copy) {
+            usages.0 |= copy;
+        }
+    }
+}
+
+/// Writes each camera's per-frame `wetness_decay` from its `dry_rate` while
+/// [`RainGlareWetness`] is enabled, and resets it to 1 otherwise.
+fn dry_rain_wetness(
+    time: Res<Time>,
+    wetness: Option<Res<RainGlareWetness>>,
+    mut q: Query<(&RainGlareSettings, &mut RainGlareDerived)>,
+) {
+    let dt = if wetness.is_some() {
+        time.delta_seconds()
+    } else {
+        0.0
+    };
+
+    for (settings, mut derived) in &mut q {
+        derived.wetness_decay = (-settings.dry_rate.max(0.0) * dt).exp();
+    }
+}
+
+/// Consumes finished readbacks: updates [`RainGlareCoverage`] and eases `threshold` towards
+/// the [`RainGlareAutoThreshold`] estimate.
+fn apply_rain_readbacks(
+    time: Res<Time>,
+    auto_threshold: Option<Res<RainGlareAutoThreshold>>,
+    coverage: Option<ResMut<RainGlareCoverage>>,
+    samples: Res<RainGlareReadbackSamples>,
+    mut targets: Local<HashMap<Entity, f32>>,
+    mut q: Query<(Entity, &mut RainGlareSettings)>,
+) {
+    let frames = std::mem::take(&mut *samples.0.lock().unwrap());
+    if auto_threshold.is_none() {
+        targets.clear();
+    }
+
+    let mut largest_coverage = None::<f32>;
+    for (entity, settings) in &mut q {
+        let weights = settings.threshold_color_weights;
+        let frame = frames.get(&entity);
+
+        if let Some(frame) = frame {
+            // Streaks only add light, so any sample the pass brightened is covered.
+            let covered = frame
+                .scene
+                .iter()
+                .zip(&frame.output)
+                .filter(|(scene, output)| output.dot(weights) - scene.dot(weights) > 0.01)
+                .count();
+            let fraction = covered as f32 / frame.scene.len().max(1) as f32;
+            largest_coverage = Some(largest_coverage.map_or(fraction, |c| c.max(fraction)));
+        }
+
+        let Some(auto_threshold) = auto_threshold.as_deref() else {
+            continue;
+        };
+        if let Some(frame) = frame {
+            // The threshold that leaves `target_coverage` of the samples above it.
+            let target_coverage = auto_threshold.target_coverage.clamp(0.0, 1.0);
+            let mut lumas: Vec<f32> = frame
+                .scene
+                .iter()
+                .map(|color| color.dot(weights))
+                .filter(|luma| luma.is_finite())
+                .collect();
+            lumas.sort_by(f32::total_cmp);
+            if let Some(last) = lumas.len().checked_sub(1) {
+                let index = (((1.0 - target_coverage) * lumas.len() as f32) as usize).min(last);
+                targets.insert(entity, lumas[index].max(0.0));
+            }
+        }
+
+        let Some(&target) = targets.get(&entity) else {
+            continue;
+        };
+        let follow = 1.0 - (-auto_threshold.rate.max(0.0) * time.delta_seconds()).exp();
+        let threshold = settings.threshold + (target - settings.threshold) * follow;
+        settings
+            .map_unchanged(|settings| &mut settings.threshold)
+            .set_if_neq(threshold);
+    }
+
+    if let (Some(mut coverage), Some(fraction)) = (coverage, largest_coverage) {
+        coverage.0 = fraction;
+    }
+}
+
+fn send_rain_settings_changed(
+    mut events: EventWriter<RainGlareSettingsChanged>,
+    mut previous: Local<HashMap<Entity, RainGlareSettings>>,
+    mut removed: RemovedComponents<RainGlareSettings>,
+    q: Query<(Entity, &RainGlareSettings), Changed<RainGlareSettings>>,
+) {
+    for entity in removed.read() {
+        previous.remove(&entity);
+    }
+
+    // Everything but the fields the auto-time system rewrites each frame.
+    let authored = |settings: &RainGlareSettings| RainGlareSettings {
+        time: 0.0,
+        view_angle_factor: 0.0,
+        ..*settings
+    };
+    for (entity, settings) in &q {
+        let settings = *settings;
+        let changed = previous
+            .insert(entity, settings)
+            .is_none_or(|previous| authored(&previous) != authored(&settings));
+        if changed {
+            events.send(RainGlareSettingsChanged { entity, settings });
+        }
+    }
+}
+
+fn update_active_rain_cameras(
+    active: Option<ResMut<RainGlareActiveCameras>>,
+    q: Query<(
+        Entity,
+        &Camera,
+        &RainGlareSettings,
+        Option<&RainGlareDerived>,
+    )>,
+) {
+    let Some(mut active) = active else {
+        return;
+    };
+
+    let mut entities: Vec<Entity> = q
+        .iter()
+        .filter(|(_, camera, settings, derived)| {
+            let derived = derived.unwrap_or(&RainGlareDerived::DEFAULT);
+            camera.is_active && RainGlareUniform::new(settings, derived).is_active()
+        })
+        .map(|(entity, ..)| entity)
+        .collect();
+    entities.sort();
+    if active.0 != entities {
+        active.0 = entities;
+    }
+}
+
+#[cfg(debug_assertions)]
+fn record_rain_history(mut q: Query<(&RainGlareSettings, &mut RainGlareHistory)>) {
+    for (settings, mut history) in &mut q {
+        history.push(*settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::system::RunSystemOnce,
+        render::{
+            MainWorld, RenderPlugin,
+            render_resource::{
+                binding_types::storage_buffer,
+                encase::{DynamicUniformBuffer, UniformBuffer},
+            },
+            renderer::{RenderInstance, RenderQueue, WgpuWrapper, initialize_renderer},
+            settings::{RenderCreation, WgpuSettings},
+        },
+        tasks::block_on,
+    };
+
+    use super::*;
+
+    fn extracted(settings: RainGlareSettings) -> RainGlareUniform {
+        RainGlareUniform::new(&settings, &RainGlareDerived::DEFAULT)
+    }
+
+    #[test]
+    fn default_settings_are_finite_and_in_range() {
+        let d = RainGlareSettings::default();
+        assert!(
+            d.to_array().iter().all(|v| v.is_finite()),
+            "RainGlareSettings::default() contains a non-finite value: {d:?}"
+        );
+
+        let unit = 0.0..=1.0;
+        for (name, value) in [
+            ("threshold", d.threshold),
+            ("rain_density", d.rain_density),
+            ("snap_to_pixel", d.snap_to_pixel),
+            ("view_angle_factor", d.view_angle_factor),
+            ("dry_patch_amount", d.dry_patch_amount),
+            ("sparkle_amount", d.sparkle_amount),
+            ("sky_only", d.sky_only),
+        ] {
+            assert!(
+                unit.contains(&value),
+                "default {name} {value} is outside 0..1"
+            );
+        }
+        for (name, value) in [
+            ("intensity", d.intensity),
+            ("streak_length_px", d.streak_length_px),
+            ("speed", d.speed),
+            ("mask_thickness_px", d.mask_thickness_px),
+            ("tail_quant_steps", d.tail_quant_steps),
+            ("edge_softness_px", d.edge_softness_px),
+            ("dry_patch_scale", d.dry_patch_scale),
+            ("core_rolloff", d.core_rolloff),
+            ("length_from_brightness", d.length_from_brightness),
+            ("head_fade", d.head_fade),
+            ("tail_fade", d.tail_fade),
+            ("highlight_bleed_px", d.highlight_bleed_px),
+            ("motion_response", d.motion_response),
+            ("beat_response", d.beat_response),
+            ("normal_follow", d.normal_follow),
+            ("cross_blur_px", d.cross_blur_px),
+            ("dry_rate", d.dry_rate),
+            ("wetness_gain", d.wetness_gain),
+            ("streak_count_scale", d.streak_count_scale),
+            ("contrast_preserve", d.contrast_preserve),
+        ] {
+            assert!(value >= 0.0, "default {name} {value} is negative");
+        }
+        assert!(d.pattern_scale > 0.0 && d.brightness_gamma > 0.0);
+        assert!(d.streak_spacing > 0.0);
+        for color in [d.head_color, d.tail_color] {
+            assert!(color.red >= 0.0 && color.green >= 0.0 && color.blue >= 0.0);
+        }
+    }
+
+    // sRGB mid grey is about 0.214 linear; black and white are unchanged.
+    #[test]
+    fn daylight_tint_is_linearized() {
+        let daylight = |tint| RainGlareDaylight {
+            tint,
+            intensity: 1.0,
+        };
+        let grey = daylight(Srgba::rgb(0.5, 0.5, 0.5)).linear_tint();
+        assert!(
+            (grey - Vec3::splat(0.214_041)).abs().max_element() < 1e-4,
+            "sRGB tint was not linearized: {grey:?}"
+        );
+        assert_eq!(daylight(Srgba::WHITE).linear_tint(), Vec3::ONE);
+        assert_eq!(daylight(Srgba::BLACK).linear_tint(), Vec3::ZERO);
+    }
+
+    #[test]
+    fn streak_gradient_is_linearized() {
+        let settings = RainGlareSettings {
+            head_color: Srgba::rgb(0.5, 0.5, 0.5),
+            tail_color: Srgba::BLACK,
+            ..default()
+        };
+        let uniform = RainGlareUniform::new(&settings, &RainGlareDerived::DEFAULT);
+        assert!(
+            (uniform.head_color - Vec3::splat(0.214_041))
+                .abs()
+                .max_element()
+                < 1e-4
+        );
+        assert_eq!(uniform.tail_color, Vec3::ZERO);
+    }
+
+    // Degenerate pattern scales reach the shader as a finite, positive value.
+    #[test]
+    fn clamp_pattern_scale_keeps_the_scale_finite() {
+        let mut world = World::new();
+        let views = [0.0, -2.0, f32::NAN].map(|pattern_scale| {
+            world
+                .spawn(extracted(RainGlareSettings {
+                    pattern_scale,
+                    ..default()
+                }))
+                .id()
+        });
+
+        world.run_system_once(clamp_pattern_scale);
+
+        for view in views {
+            let scale = world.get::<RainGlareUniform>(view).unwrap().pattern_scale;
+            assert!(scale.is_finite() && scale > 0.0, "pattern_scale {scale}");
+            assert!((1.0 / scale).is_finite());
+        }
+    }
+
+    const BLOBS: RainGlareSettings = RainGlareSettings {
+        streak_length_px: 4.0,
+        mask_thickness_px: 3.0,
+        ..RainGlareSettings::DEFAULT
+    };
+
+    // The blob warning stays quiet for the presets and fires once the streaks are wider than
+    // long.
+    #[test]
+    fn blob_streaks() {
+        assert!(!RainGlareSettings::DEFAULT.has_blob_streaks());
+        assert!(!RainGlareSettings::LIGHT_DRIZZLE.has_blob_streaks());
+        assert!(!RainGlareSettings::DOWNPOUR.has_blob_streaks());
+        assert!(BLOBS.has_blob_streaks());
+    }
+
+    #[test]
+    fn clamped_mask_thickness() {
+        assert_eq!(RainGlareSettings::clamped_mask_thickness(3.0, 4.0), 2.0);
+        assert_eq!(RainGlareSettings::clamped_mask_thickness(0.75, 96.0), 0.75);
+        assert_eq!(RainGlareSettings::clamped_mask_thickness(1.0, -4.0), 0.0);
+    }
+
+    #[test]
+    fn apply_rain_limits_clamps_thickness() {
+        let mut world = World::new();
+        world.insert_resource(RainGlareLimits {
+            clamp_thickness: true,
+            ..default()
+        });
+        let blobs = world.spawn(extracted(BLOBS)).id();
+        let default = world.spawn(extracted(RainGlareSettings::DEFAULT)).id();
+
+        world.run_system_once(apply_rain_limits);
+
+        let thickness = |view| {
+            world
+                .get::<RainGlareUniform>(view)
+                .unwrap()
+                .mask_thickness_px
+        };
+        assert_eq!(thickness(blobs), 2.0);
+        assert_eq!(
+            thickness(default),
+            RainGlareSettings::DEFAULT.mask_thickness_px
+        );
+    }
+
+    #[test]
+    fn validate_matches_the_plugin_clamps() {
+        assert_eq!(RainGlareSettings::DEFAULT.validate(), Ok(()));
+        assert_eq!(RainGlareSettings::LIGHT_DRIZZLE.validate(), Ok(()));
+        assert_eq!(RainGlareSettings::DOWNPOUR.validate(), Ok(()));
+
+        let new = |intensity, rain_density| {
+            RainGlareSettings::try_new(intensity, 0.8, 96.0, rain_density, Vec2::Y, 1.0)
+        };
+        assert!(
+            new(
+                RainGlareSettings::MAX_INTENSITY,
+                RainGlareQuality::HIGH.max_density
+            )
+            .is_ok()
+        );
+        assert!(new(RainGlareSettings::MAX_INTENSITY + 1.0, 0.5).is_err());
+        assert!(new(0.5, RainGlareQuality::HIGH.max_density + 1.0).is_err());
+
+        let field = |settings: RainGlareSettings| match settings.validate() {
+            Err(RainGlareSettingsError::OutOfRange { field, .. }) => field,
+            other => panic!("expected an out-of-range error, got {other:?}"),
+        };
+        assert_eq!(
+            field(RainGlareSettings {
+                pattern_scale: 0.0,
+                ..default()
+            }),
+            "pattern_scale"
+        );
+        assert_eq!(field(BLOBS), "mask_thickness_px");
+    }
+
+    // The WGSL struct in `rain_glare_types.wgsl` must put every member where encase writes
+    // the matching `RainGlareUniform` field.
+    #[test]
+    fn wgsl_layout_matches_the_uniform() {
+        let source: String = include_str!("../assets/rain_glare_types.wgsl")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let module = naga::front::wgsl::parse_str(&source).unwrap();
+        let Some(naga::TypeInner::Struct { members, span }) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some("RainGlareSettings"))
+            .map(|(_, ty)| &ty.inner)
+        else {
+            panic!("rain_glare_types.wgsl has no RainGlareSettings struct");
+        };
+
+        for (i, member) in members.iter().enumerate() {
+            assert_eq!(
+                u64::from(member.offset),
+                RainGlareUniform::METADATA.offset(i),
+                "offset of WGSL member {:?}",
+                member.name
+            );
+        }
+        assert_eq!(u64::from(*span), RainGlareUniform::SHADER_SIZE.get());
+        assert_eq!(u64::from(*span), SETTINGS_UNIFORM_SIZE);
+    }
+
+    /// `Core3d` with the nodes the rain glare node is ordered against, and `UiPass` if
+    /// `with_ui`.
+    fn core_3d_render_app(with_ui: bool) -> SubApp {
+        use bevy::render::render_graph::EmptyNode;
+
+        let mut render_app = SubApp::new();
+        render_app
+            .init_resource::<RenderGraph>()
+            .add_render_sub_graph(Core3d);
+        for node in [
+            Node3d::MainTransmissivePass.intern(),
+            Node3d::MainTransparentPass.intern(),
+            Node3d::Tonemapping.intern(),
+            Node3d::EndMainPassPostProcessing.intern(),
+            Node3d::Upscaling.intern(),
+        ] {
+            render_app.add_render_graph_node::<EmptyNode>(Core3d, node);
+        }
+        if with_ui {
+            render_app.add_render_graph_node::<EmptyNode>(Core3d, NodeUi::UiPass);
+        }
+        render_app
+    }
+
+    fn assert_rain_glare_between(
+        render_app: &SubApp,
+        before: impl RenderLabel,
+        after: impl RenderLabel,
+    ) {
+        use bevy::render::render_graph::Edge;
+
+        let core_3d = render_app
+            .world()
+            .resource::<RenderGraph>()
+            .get_sub_graph(Core3d)
+            .unwrap();
+        let node = RainGlareLabel.intern();
+        assert!(core_3d.get_node_state(node).is_ok());
+        for edge in [
+            Edge::NodeEdge {
+                output_node: before.intern(),
+                input_node: node,
+            },
+            Edge::NodeEdge {
+                output_node: node,
+                input_node: after.intern(),
+            },
+        ] {
+            assert!(
+                core_3d.has_edge(&edge),
+                "missing render graph edge {edge:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn node_runs_after_tonemapping_by_default() {
+        let mut render_app = core_3d_render_app(false);
+        wire_rain_glare_node(&mut render_app, RainGlareLayerDepth::default());
+        assert_rain_glare_between(
+            &render_app,
+            Node3d::Tonemapping,
+            Node3d::EndMainPassPostProcessing,
+        );
+    }
+
+    #[test]
+    fn node_runs_before_transparent() {
+        let mut render_app = core_3d_render_app(false);
+        wire_rain_glare_node(&mut render_app, RainGlareLayerDepth::BeforeTransparent);
+        assert_rain_glare_between(
+            &render_app,
+            Node3d::MainTransmissivePass,
+            Node3d::MainTransparentPass,
+        );
+    }
+
+    #[test]
+    fn node_runs_over_ui() {
+        let mut render_app = core_3d_render_app(true);
+        wire_rain_glare_node(&mut render_app, RainGlareLayerDepth::OverUi);
+        assert_rain_glare_between(&render_app, NodeUi::UiPass, Node3d::Upscaling);
+
+        // Without the UI pass it falls back to running after tonemapping.
+        let mut render_app = core_3d_render_app(false);
+        wire_rain_glare_node(&mut render_app, RainGlareLayerDepth::OverUi);
+        assert_rain_glare_between(
+            &render_app,
+            Node3d::Tonemapping,
+            Node3d::EndMainPassPostProcessing,
+        );
+    }
+
+    /// Headless app with the plugin added on top of `render_app`, if any. The plugin is only
+    /// built, not finished, so no GPU is needed.
+    fn headless_app(render_app: Option<SubApp>) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), TransformPlugin))
+            .init_asset::<Shader>()
+            .init_resource::<Msaa>();
+        if let Some(render_app) = render_app {
+            app.insert_sub_app(RenderApp, render_app);
+        }
+        app.add_plugins(RainGlarePlugin::default());
+        app
+    }
+
+    #[test]
+    fn plugin_can_be_added_twice() {
+        let other = || {
+            RainGlarePlugin::default()
+                .layer_depth(RainGlareLayerDepth::OverUi)
+                .with_hot_reload_path("custom_rain_glare.wgsl")
+                .without_auto_time()
+        };
+        let mut app = headless_app(Some(core_3d_render_app(true)));
+        app.add_plugins(other());
+
+        // Only the first instance is wired in.
+        assert_rain_glare_between(
+            app.sub_app(RenderApp),
+            Node3d::Tonemapping,
+            Node3d::EndMainPassPostProcessing,
+        );
+        assert_eq!(
+            app.world().resource::<RainGlarePluginInstalled>().0,
+            RainGlarePlugin::default().config()
+        );
+        app.world_mut().spawn(RainGlareCameraBundle::default());
+        app.update();
+        app.update();
+
+        // `finish` keeps the first instance's shader, not the second's hot-reload file.
+        let mut app = headless_app(None);
+        app.add_plugins(other());
+        app.finish();
+        assert_eq!(
+            app.world().resource::<RainGlareShader>().0,
+            RAIN_GLARE_SHADER_HANDLE
+        );
+    }
+
+    #[test]
+    fn inactive_camera_is_skipped() {
+        let mut app = headless_app(None);
+        app.init_resource::<RainGlareActiveCameras>();
+        let mut spawn_camera = |is_active| {
+            let mut bundle = RainGlareCameraBundle::default();
+            bundle.camera.camera.is_active = is_active;
+            app.world_mut()
+                .spawn((bundle, RainGlareTimeSource::Manual(5.0)))
+                .id()
+        };
+        let active = spawn_camera(true);
+        let inactive = spawn_camera(false);
+
+        app.update();
+
+        let time = |camera| app.world().get::<RainGlareSettings>(camera).unwrap().time;
+        assert_eq!(time(active), 5.0);
+        assert_eq!(time(inactive), RainGlareSettings::DEFAULT.time);
+        assert_eq!(
+            app.world().resource::<RainGlareActiveCameras>().0,
+            vec![active]
+        );
+    }
+
+    #[test]
+    fn view_angle_factor_waits_for_transform_propagation() {
+        let mut app = headless_app(None);
+        // Past `PostStartup`, which would already propagate a camera spawned up front.
+        app.update();
+
+        let looking_down = Transform::from_xyz(0.0, 10.0, 0.0).looking_to(Vec3::NEG_Y, Vec3::Z);
+        let mut bundle = RainGlareCameraBundle::from_transform(looking_down);
+        bundle.settings.view_angle_factor = 0.5;
+        let camera = app.world_mut().spawn(bundle).id();
+        let view_angle_factor = |app: &App| {
+            app.world()
+                .get::<RainGlareSettings>(camera)
+                .unwrap()
+                .view_angle_factor
+        };
+
+        // Still the identity GlobalTransform in `Update`, which would read as looking at the
+        // horizon (factor 1); the value is kept instead.
+        app.update();
+        assert_eq!(view_angle_factor(&app), 0.5);
+
+        app.update();
+        assert!(
+            view_angle_factor(&app) < 1e-4,
+            "{}",
+            view_angle_factor(&app)
+        );
+
+        // A child camera with an identity local transform is just as unpropagated.
+        let parent = app
+            .world_mut()
+            .spawn(SpatialBundle::from_transform(looking_down))
+            .id();
+        let mut bundle = RainGlareCameraBundle::default();
+        bundle.settings.view_angle_factor = 0.5;
+        let child = app.world_mut().spawn(bundle).set_parent(parent).id();
+        let child_factor = |app: &App| {
+            app.world()
+                .get::<RainGlareSettings>(child)
+                .unwrap()
+                .view_angle_factor
+        };
+        app.update();
+        assert_eq!(child_factor(&app), 0.5);
+        app.update();
+        assert!(child_factor(&app) < 1e-4, "{}", child_factor(&app));
+    }
+
+    #[test]
+    fn readback_keeps_hdr_values() {
+        let grid = (READBACK_GRID * READBACK_GRID) as usize;
+        let mut data = vec![0u8; (2 * READBACK_GRID * READBACK_ROW_BYTES) as usize];
+        for (row, bytes) in data
+            .chunks_exact_mut(READBACK_ROW_BYTES as usize)
+            .enumerate()
+        {
+            let value = if row < READBACK_GRID as usize {
+                4.0
+            } else {
+                0.25
+            };
+            for texel in bytes[..(READBACK_GRID * 8) as usize].chunks_exact_mut(8) {
+                for channel in texel.chunks_exact_mut(2) {
+                    channel.copy_from_slice(&f16::from_f32(value).to_le_bytes());
+                }
+            }
+        }
+        let frame = decode_readback(&data);
+        assert_eq!(frame.scene, vec![Vec3::splat(4.0); grid]);
+        assert_eq!(frame.output, vec![Vec3::splat(0.25); grid]);
+
+        // The threshold follows an HDR scene above 1.
+        let mut app = headless_app(None);
+        app.insert_resource(RainGlareAutoThreshold {
+            target_coverage: 0.5,
+            rate: 1e6,
+        });
+        let camera = app.world_mut().spawn(RainGlareCameraBundle::default()).id();
+        app.update();
+        app.world()
+            .resource::<RainGlareReadbackSamples>()
+            .0
+            .lock()
+            .unwrap()
+            .insert(camera, frame);
+        app.update();
+        let threshold = app
+            .world()
+            .get::<RainGlareSettings>(camera)
+            .unwrap()
+            .threshold;
+        assert!((threshold - 4.0).abs() < 1e-3, "{threshold}");
+    }
+
+    #[test]
+    fn settings_changed_ignores_auto_time() {
+        let mut app = headless_app(None);
+        let camera = app.world_mut().spawn(RainGlareCameraBundle::default()).id();
+        let sent = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<Events<RainGlareSettingsChanged>>()
+                .drain()
+                .map(|event| event.entity)
+                .collect::<Vec<_>>()
+        };
+
+        app.update();
+        assert_eq!(sent(&mut app), vec![camera]);
+
+        // Only `time` and `view_angle_factor` move from here on.
+        app.update();
+        app.update();
+        assert_eq!(sent(&mut app), vec![]);
+
+        app.world_mut()
+            .get_mut::<RainGlareSettings>(camera)
+            .unwrap()
+            .rain_density = 0.25;
+        app.update();
+        assert_eq!(sent(&mut app), vec![camera]);
+    }
+
+    #[test]
+    fn post_update_time_sees_this_frames_transform() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), TransformPlugin))
+            .init_asset::<Shader>()
+            .init_resource::<Msaa>()
+            .add_plugins(RainGlarePlugin::default().time_schedule(PostUpdate));
+        app.update();
+
+        let looking_down = Transform::from_xyz(0.0, 10.0, 0.0).looking_to(Vec3::NEG_Y, Vec3::Z);
+        let camera = app
+            .world_mut()
+            .spawn(RainGlareCameraBundle::from_transform(looking_down))
+            .id();
+        app.update();
+        let factor = app
+            .world()
+            .get::<RainGlareSettings>(camera)
+            .unwrap()
+            .view_angle_factor;
+        assert!(factor < 1e-4, "{factor}");
+    }
+
+    /// What each rain camera's shader saw in the last frame: its offset into
+    /// `ComponentUniforms<RainGlareUniform>` and the settings struct read from there.
+    #[derive(Resource, Default)]
+    struct UploadedUniforms(HashMap<Entity, (u32, Vec<u8>)>);
+
+    /// Binds the settings uniform at each view's dynamic offset, as the node does, and copies
+    /// the struct the shader sees out to a readable buffer.
+    fn read_back_uniforms(
+        device: Res<RenderDevice>,
+        queue: Res<RenderQueue>,
+        uniforms: Res<ComponentUniforms<RainGlareUniform>>,
+        views: Query<(Entity, &DynamicUniformIndex<RainGlareUniform>)>,
+        mut uploaded: ResMut<UploadedUniforms>,
+    ) {
+        uploaded.0.clear();
+        let Some(binding) = uniforms.binding() else {
+            return;
+        };
+
+        let types: String = include_str!("../assets/rain_glare_types.wgsl")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Wgsl(
+                format!(
+                    "{types}
+                    @group(0) @binding(0) var<uniform> settings: RainGlareSettings;
+                    @group(0) @binding(1) var<storage, read_write> seen: RainGlareSettings;
+                    @compute @workgroup_size(1)
+                    fn main() {{ seen = settings; }}"
+                )
+                .into(),
+            ),
+        });
+        let layout = device.create_bind_group_layout(
+            None,
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer::<RainGlareUniform>(true),
+                    storage_buffer::<RainGlareUniform>(false),
+                ),
+            ),
+        );
+        let pipeline = device.create_compute_pipeline(&RawComputePipelineDescriptor {
+            label: None,
+            layout: Some(&device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            })),
+            module: &module,
+            entry_point: "main",
+            compilation_options: default(),
+        });
+
+        let size = RainGlareUniform::SHADER_SIZE.get();
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+        let mut readbacks = Vec::new();
+        for (entity, index) in &views {
+            let seen = device.create_buffer(&BufferDescriptor {
+                label: None,
+                size,
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let readback = device.create_buffer(&BufferDescriptor {
+                label: None,
+                size,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(
+                None,
+                &layout,
+                &BindGroupEntries::sequential((binding.clone(), seen.as_entire_binding())),
+            );
+            {
+                let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[index.index()]);
+                pass.dispatch_workgroups(1, 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&seen, 0, &readback, 0, size);
+            readbacks.push((entity, index.index(), readback));
+        }
+        queue.submit([encoder.finish()]);
+
+        for (.., readback) in &readbacks {
+            readback
+                .slice(..)
+                .map_async(MapMode::Read, |result| result.unwrap());
+        }
+        device.poll(Maintain::Wait);
+        for (entity, offset, readback) in readbacks {
+            let bytes = readback.slice(..).get_mapped_range().to_vec();
+            uploaded.0.insert(entity, (offset, bytes));
+        }
+    }
+
+    /// App with a real render world that runs `RainGlareSettings` through extraction and
+    /// `ComponentUniforms` on the GPU. Tests using it are `#[ignore]`d, as CI may have no
+    /// adapter; the `extraction_app` tests cover the same path on the CPU.
+    fn uniform_upload_app() -> App {
+        let instance = wgpu::Instance::default();
+        let adapter_options = default();
+        let (device, queue, adapter_info, adapter) = block_on(initialize_renderer(
+            &instance,
+            &WgpuSettings::default(),
+            &adapter_options,
+        ));
+        let instance = RenderInstance(Arc::new(WgpuWrapper::new(instance)));
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            WindowPlugin {
+                primary_window: None,
+                ..default()
+            },
+            AssetPlugin::default(),
+            RenderPlugin {
+                render_creation: RenderCreation::manual(
+                    device,
+                    queue,
+                    adapter_info,
+                    adapter,
+                    instance,
+                ),
+                ..default()
+            },
+            ImagePlugin::default(),
+            ExtractComponentPlugin::<RainGlareSettings>::default(),
+            UniformComponentPlugin::<RainGlareUniform>::default(),
+        ));
+        app.sub_app_mut(RenderApp)
+            .init_resource::<UploadedUniforms>()
+            .add_systems(
+                Render,
+                read_back_uniforms.in_set(RenderSet::PrepareBindGroups),
+            );
+        app.finish();
+        app.cleanup();
+        app
+    }
+
+    /// The uniform offset `camera` was rendered with in `app`'s last frame, and the settings
+    /// struct its shader saw there.
+    fn uploaded(app: &App, camera: Entity) -> &(u32, Vec<u8>) {
+        let uploaded = app
+            .sub_app(RenderApp)
+            .world()
+            .resource::<UploadedUniforms>();
+        &uploaded.0[&camera]
+    }
+
+    fn encoded(uniform: &RainGlareUniform) -> Vec<u8> {
+        let mut buffer = UniformBuffer::new(Vec::new());
+        buffer.write(uniform).unwrap();
+        buffer.into_inner()
+    }
+
+    /// App whose render world only runs `RainGlareSettings` through `ExtractSchedule`, the
+    /// way `RenderPlugin` does, so the extracted uniforms can be checked without a GPU.
+    fn extraction_app() -> App {
+        let mut render_app = SubApp::new();
+        render_app.set_extract(|main_world, render_world| {
+            // `RenderPlugin` clears the render world at the end of every frame.
+            render_world.clear_entities();
+            let mut extracted = MainWorld::default();
+            std::mem::swap(main_world, &mut extracted);
+            render_world.insert_resource(extracted);
+            render_world.run_schedule(ExtractSchedule);
+            let mut extracted = render_world.remove_resource::<MainWorld>().unwrap();
+            std::mem::swap(main_world, &mut extracted);
+        });
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_sub_app(RenderApp, render_app);
+        app.add_plugins(ExtractComponentPlugin::<RainGlareSettings>::default());
+        app
+    }
+
+    /// The uniform `camera`'s main-world settings and derived values should extract to.
+    fn expected_uniform(app: &App, camera: Entity) -> Vec<u8> {
+        let camera = app.world().entity(camera);
+        encoded(&RainGlareUniform::new(
+            camera.get::<RainGlareSettings>().unwrap(),
+            camera
+                .get::<RainGlareDerived>()
+                .unwrap_or(&RainGlareDerived::DEFAULT),
+        ))
+    }
+
+    /// Each extracted camera's offset and bytes in `ComponentUniforms`, laid out as
+    /// `prepare_uniform_components` writes them: in query order, each encoded at the
+    /// largest minimum uniform offset alignment wgpu allows.
+    fn prepared_uniforms(app: &mut App) -> HashMap<Entity, (u32, Vec<u8>)> {
+        let world = app.sub_app_mut(RenderApp).world_mut();
+        let mut buffer = DynamicUniformBuffer::new_with_alignment(Vec::new(), 256);
+        let offsets: Vec<_> = world
+            .query::<(Entity, &RainGlareUniform)>()
+            .iter(world)
+            .map(|(entity, uniform)| (entity, buffer.write(uniform).unwrap() as u32))
+            .collect();
+        let bytes = buffer.into_inner();
+        let size = RainGlareUniform::SHADER_SIZE.get() as usize;
+        offsets
+            .into_iter()
+            .map(|(entity, offset)| {
+                let start = offset as usize;
+                (entity, (offset, bytes[start..start + size].to_vec()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extracted_settings_reach_the_uniform_buffer() {
+        let mut app = extraction_app();
+        let cameras: Vec<_> = [0.25, 0.5, 0.75]
+            .into_iter()
+            .map(|intensity| {
+                let settings = RainGlareSettings {
+                    intensity,
+                    wind: Vec2::new(0.3, 1.0),
+                    head_color: Srgba::rgb(1.0, 0.5, 0.25),
+                    ..RainGlareSettings::DOWNPOUR
+                };
+                let derived = RainGlareDerived {
+                    outdoor_amount: 1.0 - intensity,
+                    rain_amount: 0.8,
+                    ..RainGlareDerived::DEFAULT
+                };
+                app.world_mut().spawn((settings, derived)).id()
+            })
+            .collect();
+
+        app.update();
+
+        let prepared = prepared_uniforms(&mut app);
+        assert_eq!(prepared.len(), cameras.len());
+        let mut offsets = Vec::new();
+        for camera in cameras {
+            let expected = expected_uniform(&app, camera);
+            let extracted = app
+                .sub_app(RenderApp)
+                .world()
+                .get::<RainGlareUniform>(camera)
+                .unwrap();
+            assert_eq!(encoded(extracted), expected);
+            let (offset, bytes) = &prepared[&camera];
+            assert_eq!(*bytes, expected);
+            offsets.push(*offset);
+        }
+        offsets.sort_unstable();
+        offsets.dedup();
+        assert_eq!(offsets.len(), 3, "cameras share a uniform offset");
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn extracted_settings_reach_the_uniform_buffer_on_gpu() {
+        let mut app = uniform_upload_app();
+        let settings = RainGlareSettings {
+            intensity: 0.75,
+            rain_density: 0.4,
+            wind: Vec2::new(0.3, 1.0),
+            head_color: Srgba::rgb(1.0, 0.5, 0.25),
+            ..RainGlareSettings::DOWNPOUR
+        };
+        let cameras: Vec<_> = [1.0, 0.5, 0.25]
+            .into_iter()
+            .map(|outdoor_amount| {
+                let derived = RainGlareDerived {
+                    outdoor_amount,
+                    rain_amount: 0.8,
+                    ..RainGlareDerived::DEFAULT
+                };
+                let camera = app.world_mut().spawn((settings, derived)).id();
+                (camera, derived)
+            })
+            .collect();
+
+        app.update();
+
+        let mut offsets = Vec::new();
+        for (camera, derived) in cameras {
+            let (offset, seen) = uploaded(&app, camera);
+            assert_eq!(*seen, encoded(&RainGlareUniform::new(&settings, &derived)));
+            offsets.push(*offset);
+        }
+        offsets.sort_unstable();
+        offsets.dedup();
+        assert_eq!(offsets.len(), 3, "cameras share a uniform offset");
+    }
+
+    #[test]
+    fn each_camera_reads_its_own_settings() {
+        let mut app = extraction_app();
+        let drizzle = app.world_mut().spawn(RainGlareSettings::LIGHT_DRIZZLE).id();
+        let downpour = app.world_mut().spawn(RainGlareSettings::DOWNPOUR).id();
+
+        app.update();
+
+        let prepared = prepared_uniforms(&mut app);
+        let (drizzle_offset, drizzle_seen) = &prepared[&drizzle];
+        let (downpour_offset, downpour_seen) = &prepared[&downpour];
+        assert_ne!(drizzle_offset, downpour_offset);
+        assert_eq!(*drizzle_seen, expected_uniform(&app, drizzle));
+        assert_eq!(*downpour_seen, expected_uniform(&app, downpour));
+        assert_ne!(drizzle_seen, downpour_seen);
+
+        // Offsets are reassigned every frame; the remaining camera keeps its own settings.
+        app.world_mut().despawn(drizzle);
+        app.update();
+        let prepared = prepared_uniforms(&mut app);
+        assert_eq!(prepared.len(), 1);
+        assert_eq!(prepared[&downpour].1, expected_uniform(&app, downpour));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn each_camera_reads_its_own_settings_on_gpu() {
+        let mut app = uniform_upload_app();
+        let drizzle = app
+            .world_mut()
+            .spawn((RainGlareSettings::LIGHT_DRIZZLE, RainGlareDerived::DEFAULT))
+            .id();
+        let downpour = app
+            .world_mut()
+            .spawn((RainGlareSettings::DOWNPOUR, RainGlareDerived::DEFAULT))
+            .id();
+
+        app.update();
+
+        let (drizzle_offset, drizzle_seen) = uploaded(&app, drizzle).clone();
+        let (downpour_offset, downpour_seen) = uploaded(&app, downpour).clone();
+        assert_ne!(drizzle_offset, downpour_offset);
+        assert_eq!(
+            drizzle_seen,
+            encoded(&extracted(RainGlareSettings::LIGHT_DRIZZLE))
+        );
+        assert_eq!(
+            downpour_seen,
+            encoded(&extracted(RainGlareSettings::DOWNPOUR))
+        );
+
+        // Offsets are reassigned every frame; the remaining camera keeps its own settings.
+        app.world_mut().despawn(drizzle);
+        app.update();
+        assert_eq!(
+            uploaded(&app, downpour).1,
+            encoded(&extracted(RainGlareSettings::DOWNPOUR))
+        );
+    }
+
+    #[test]
+    fn entry_points_come_from_the_composed_shader() {
+        let fullscreen = Shader::from_wgsl(
+            "#define_import_path bevy_core_pipeline::fullscreen_vertex_shader
+            struct FullscreenVertexOutput {
+                @builtin(position) position: vec4<f32>,
+                @location(0) uv: vec2<f32>,
+            };",
+            "fullscreen.wgsl",
+        );
+        let types = Shader::from_wgsl(
+            include_str!("../assets/rain_glare_types.wgsl"),
+            "rain_glare_types.wgsl",
+        );
+        let effect =
+            Shader::from_wgsl(include_str!("../assets/rain_glare.wgsl"), "rain_glare.wgsl");
+        let variants = Shader::from_wgsl(
+            "#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+            // @fragment fn fragment_commented(in: FullscreenVertexOutput) {}
+            @fragment // cheap
+            fn fragment_cheap(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+                return vec4<f32>(in.uv, 0.0, 1.0);
+            }
+            #ifdef FANCY
+            @fragment fn fragment_fancy(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+                return vec4<f32>(1.0);
+            }
+            #endif",
+            "variants.wgsl",
+        );
+        let ids: Vec<AssetId<Shader>> = (0..4)
+            .map(|i| Handle::<Shader>::weak_from_u128(i).id())
+            .collect();
+        let mut shaders: HashMap<_, _> = ids
+            .iter()
+            .copied()
+            .zip([fullscreen, types, effect, variants])
+            .collect();
+
+        assert_eq!(
+            fragment_entry_points(&shaders, ids[2], &[]),
+            Some(vec!["fragment".to_string()])
+        );
+        assert_eq!(
+            fragment_entry_points(&shaders, ids[3], &[]),
+            Some(vec!["fragment_cheap".to_string()])
+        );
+        assert_eq!(
+            fragment_entry_points(&shaders, ids[3], &["FANCY".into()]),
+            Some(vec![
+                "fragment_cheap".to_string(),
+                "fragment_fancy".to_string()
+            ])
+        );
+
+        // Unknown until the imports have loaded.
+        shaders.remove(&ids[0]);
+        assert_eq!(fragment_entry_points(&shaders, ids[3], &[]), None);
+    }
+
+    #[test]
+    fn viewport_rect_covers_each_eye() {
+        let target = UVec2::new(2000, 1000);
+        let eye = |x| Viewport {
+            physical_position: UVec2::new(x, 0),
+            physical_size: UVec2::new(1000, 1000),
+            ..default()
+        };
+        assert_eq!(
+            viewport_rect(Some(&eye(0)), Some(target)),
+            Vec4::new(0.0, 0.0, 0.5, 1.0)
+        );
+        assert_eq!(
+            viewport_rect(Some(&eye(1000)), Some(target)),
+            Vec4::new(0.5, 0.0, 0.5, 1.0)
+        );
+        let whole = RainGlareDerived::DEFAULT.viewport;
+        assert_eq!(whole, Vec4::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(viewport_rect(None, Some(target)), whole);
+        assert_eq!(viewport_rect(Some(&eye(1000)), None), whole);
+        assert_eq!(
+            viewport_rect(Some(&eye(1500)), Some(target)),
+            Vec4::new(0.75, 0.0, 0.25, 1.0)
+        );
+    }
+
+    #[test]
+    fn virtual_resolution_renders_a_fixed_size_mask() {
+        let native = Extent3d {
+            width: 1920,
+            height: 1080,
+            depth_or_array_layers: 1,
+        };
+        let uniform = |virtual_resolution| {
+            let derived = RainGlareDerived {
+                virtual_resolution,
+                ..RainGlareDerived::DEFAULT
+            };
+            RainGlareUniform::new(&RainGlareSettings::default(), &derived)
+        };
+
+        let low_res = uniform(Vec2::new(640.0, 360.0));
+        let virtual_size = Extent3d {
+            width: 640,
+            height: 360,
+            depth_or_array_layers: 1,
+        };
+        assert_eq!(mask_size(&low_res, native, false), Some(virtual_size));
+        assert_eq!(mask_size(&low_res, native, true), Some(virtual_size));
+        let native_res = uniform(Vec2::ZERO);
+        assert_eq!(mask_size(&native_res, native, true), Some(native));
+        assert_eq!(mask_size(&native_res, native, false), None);
+
+        // `effect_uv` snaps each native pixel center to its virtual pixel's center, and
+        // `effect_mask` loads the texel under it: each 3x3 block reads one virtual pixel.
+        let virtual_dims = low_res.virtual_size().unwrap().as_vec2();
+        let native_dims = Vec2::new(1920.0, 1080.0);
+        for native_px in [
+            UVec2::ZERO,
+            UVec2::new(2, 2),
+            UVec2::new(3, 0),
+            UVec2::new(1000, 500),
+            UVec2::new(1919, 1079),
+        ] {
+            let uv = (native_px.as_vec2() + 0.5) / native_dims;
+            let snapped = ((uv * virtual_dims).floor() + 0.5) / virtual_dims;
+            let texel = (snapped * virtual_dims).as_uvec2();
+            assert_eq!(texel, native_px / 3, "native pixel {native_px}");
+        }
+    }
+
+    #[test]
+    fn overlay_2d_camera_gets_no_rain() {
+        use bevy::core_pipeline::core_2d::graph::Core2d;
+
+        let spawn_cameras = |app: &mut App| {
+            let camera_3d = app
+                .world_mut()
+                .spawn((Camera3dBundle::default(), RainGlareSettings::default()))
+                .id();
+            let overlay = Camera {
+                order: 1,
+                ..default()
+            };
+            app.world_mut().spawn(Camera2dBundle {
+                camera: overlay,
+                ..default()
+            });
+            camera_3d
+        };
+
+        let mut app = extraction_app();
+        let camera_3d = spawn_cameras(&mut app);
+        app.update();
+        let render_world = app.sub_app_mut(RenderApp).world_mut();
+        let uniforms: Vec<Entity> = render_world
+            .query_filtered::<Entity, With<RainGlareUniform>>()
+            .iter(render_world)
+            .collect();
+        assert_eq!(uniforms, [camera_3d]);
+
+        let mut render_app = core_3d_render_app(false);
+        render_app.add_render_sub_graph(Core2d);
+        let mut app = headless_app(Some(render_app));
+        app.init_resource::<RainGlareActiveCameras>();
+        let camera_3d = spawn_cameras(&mut app);
+        app.update();
+        assert_eq!(
+            app.world().resource::<RainGlareActiveCameras>().0,
+            [camera_3d]
+        );
+        let graph = app.sub_app(RenderApp).world().resource::<RenderGraph>();
+        assert!(
+            graph
+                .get_sub_graph(Core2d)
+                .unwrap()
+                .get_node_state(RainGlareLabel)
+                .is_err()
+        );
+        assert!(
+            graph
+                .get_sub_graph(Core3d)
+                .unwrap()
+                .get_node_state(RainGlareLabel)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn view_format_pipelines_stay_bounded() {
+        let mut pipelines: HashMap<TextureFormat, u32> = RainGlarePipeline::expected_formats()
+            .into_iter()
+            .zip(0..)
+            .collect();
+        let frames = [
+            vec![
+                TextureFormat::bevy_default(),
+                ViewTarget::TEXTURE_FORMAT_HDR,
+            ],
+            vec![TextureFormat::Rgba16Unorm, TextureFormat::Rgba16Unorm],
+            vec![
+                TextureFormat::Rgb10a2Unorm,
+                TextureFormat::Rgba16Unorm,
+                TextureFormat::Rgb10a2Unorm,
+            ],
+            vec![TextureFormat::bevy_default(), TextureFormat::Rgb10a2Unorm],
+        ];
+        let mut distinct: Vec<TextureFormat> = pipelines.keys().copied().collect();
+        for formats in frames.iter().cycle().take(frames.len() * 3) {
+            for format in unqueued_formats(&pipelines, formats.iter().copied()) {
+                let id = pipelines.len() as u32;
+                assert!(
+                    pipelines.insert(format, id).is_none(),
+                    "{format:?} queued twice"
+                );
+            }
+            for format in formats {
+                if !distinct.contains(format) {
+                    distinct.push(*format);
+                }
+            }
+            assert_eq!(pipelines.len(), distinct.len());
+        }
+        assert_eq!(pipelines.len(), 4);
+    }
+}